@@ -0,0 +1,24 @@
+use inth_oauth2_async::Client;
+use inth_oauth2_async::provider::google::Installed;
+
+#[tokio::main]
+async fn main() {
+    let http_client = reqwest::Client::new();
+
+    let client = Client::new(
+        Installed,
+        String::from("143225766783-ip2d9qv6sdr37276t77luk6f7bhd6bj5.apps.googleusercontent.com"),
+        String::from("3kZ5WomzHFlN2f_XbhkyPd3o"),
+        None,
+    );
+
+    let token = client
+        .authorize_loopback(
+            &http_client,
+            Some("https://www.googleapis.com/auth/userinfo.email"),
+            |auth_uri| println!("{}", auth_uri),
+        )
+        .await
+        .unwrap();
+    println!("{:?}", token);
+}
@@ -0,0 +1,302 @@
+//! Client authentication.
+//!
+//! Requests to the token endpoint and other authenticated endpoints carry the client's
+//! credentials as chosen by [`ClientAuth`]. The HTTP client only sends the resulting headers and
+//! form fields.
+//!
+//! See [RFC 6749, section 2.3](http://tools.ietf.org/html/rfc6749#section-2.3) and
+//! [OpenID Connect Core 1.0, section 9](https://openid.net/specs/openid-connect-core-1_0.html#ClientAuthentication).
+
+use std::fmt;
+use std::sync::Arc;
+
+use serde_json::{json, Value};
+use url::form_urlencoded::Serializer;
+use url::Url;
+
+use crate::client::http_client::{HttpRequest, Method, ACCEPT};
+use crate::client::{Client, ClientError};
+use crate::jwt::{self, Signer};
+use crate::provider::Provider;
+use crate::util::random_token;
+
+/// `client_assertion_type` of JWT client assertions.
+///
+/// See [RFC 7523, section 2.2](https://tools.ietf.org/html/rfc7523#section-2.2).
+const JWT_BEARER_ASSERTION_TYPE: &str = "urn:ietf:params:oauth:client-assertion-type:jwt-bearer";
+
+/// Lifetime of client assertions, in seconds.
+const ASSERTION_LIFETIME: u64 = 60;
+
+/// Client authentication methods.
+///
+/// Each corresponds to a `token_endpoint_auth_method` value.
+#[derive(Clone, Default)]
+pub enum ClientAuth {
+    /// Client ID and secret in an HTTP Basic `Authorization` header.
+    #[default]
+    ClientSecretBasic,
+
+    /// Client ID and secret as `client_id` and `client_secret` form fields.
+    ClientSecretPost,
+
+    /// No authentication, for public clients: only the `client_id` form field is sent.
+    None,
+
+    /// A JWT assertion signed with `HS256` using the client secret.
+    ClientSecretJwt,
+
+    /// A JWT assertion signed with the client's private key.
+    ///
+    /// The signer may be a [`PrivateKey`](crate::jwt::PrivateKey), a key held elsewhere, or
+    /// [`SigningKeys`](crate::jwt::SigningKeys) during key rotation.
+    PrivateKeyJwt(Arc<dyn Signer>),
+}
+
+impl ClientAuth {
+    /// Authenticates with JWT assertions signed by `signer`.
+    pub fn private_key_jwt(signer: impl Signer + 'static) -> Self {
+        ClientAuth::PrivateKeyJwt(Arc::new(signer))
+    }
+
+    /// Returns the `token_endpoint_auth_method` value of the method.
+    ///
+    /// See [RFC 7591, section 2](https://tools.ietf.org/html/rfc7591#section-2).
+    pub fn method(&self) -> &'static str {
+        match *self {
+            ClientAuth::ClientSecretBasic => "client_secret_basic",
+            ClientAuth::ClientSecretPost => "client_secret_post",
+            ClientAuth::None => "none",
+            ClientAuth::ClientSecretJwt => "client_secret_jwt",
+            ClientAuth::PrivateKeyJwt(_) => "private_key_jwt",
+        }
+    }
+
+    /// Parses a `token_endpoint_auth_method` value.
+    ///
+    /// Returns `None` for `private_key_jwt`, which needs a key, and for unknown methods.
+    pub fn from_method(method: &str) -> Option<Self> {
+        match method {
+            "client_secret_basic" => Some(ClientAuth::ClientSecretBasic),
+            "client_secret_post" => Some(ClientAuth::ClientSecretPost),
+            "none" => Some(ClientAuth::None),
+            "client_secret_jwt" => Some(ClientAuth::ClientSecretJwt),
+            _ => None,
+        }
+    }
+}
+
+/// Private key methods are equal only if they share the same signer.
+impl PartialEq for ClientAuth {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (ClientAuth::PrivateKeyJwt(a), ClientAuth::PrivateKeyJwt(b)) => Arc::ptr_eq(a, b),
+            _ => self.method() == other.method(),
+        }
+    }
+}
+
+impl Eq for ClientAuth {}
+
+impl fmt::Debug for ClientAuth {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        f.write_str(self.method())
+    }
+}
+
+impl<P: Provider> Client<P> {
+    /// Builds an authenticated form POST to one of the provider's endpoints.
+    pub(crate) async fn authenticated_request(
+        &self,
+        uri: &Url,
+        body: String,
+    ) -> Result<HttpRequest, ClientError> {
+        let mut request = HttpRequest::new(Method::Post, uri.as_str())
+            .header(ACCEPT, "application/json");
+
+        // Serializer can't go across await points. See https://github.com/servo/rust-url/pull/550
+        let assertion = match self.auth {
+            ClientAuth::ClientSecretJwt => {
+                let claims = self.assertion_claims();
+                Some(jwt::encode_hs256(&claims, self.client_secret.as_bytes()))
+            }
+            ClientAuth::PrivateKeyJwt(ref signer) => {
+                Some(jwt::encode(&self.assertion_claims(), &**signer).await?)
+            }
+            _ => None,
+        };
+        let mut body = Serializer::new(body);
+
+        match self.auth {
+            ClientAuth::ClientSecretBasic => {
+                request = request.basic_auth(&self.client_id, &self.client_secret);
+            }
+            ClientAuth::ClientSecretPost => {
+                body.append_pair("client_id", &self.client_id);
+                body.append_pair("client_secret", &self.client_secret);
+            }
+            ClientAuth::None => {
+                body.append_pair("client_id", &self.client_id);
+            }
+            ClientAuth::ClientSecretJwt | ClientAuth::PrivateKeyJwt(_) => {}
+        }
+        if let Some(assertion) = assertion {
+            append_assertion(&mut body, &self.client_id, &assertion);
+        }
+
+        Ok(request.form(body.finish()))
+    }
+
+    /// Whether requests must carry `client_id` themselves, since [`ClientAuth::ClientSecretBasic`]
+    /// leaves it out of the body. Other methods add it, and it must not be repeated.
+    pub(crate) fn needs_client_id(&self) -> bool {
+        matches!(self.auth, ClientAuth::ClientSecretBasic)
+    }
+
+    /// Returns the claims of a client assertion, whose audience is the token endpoint.
+    ///
+    /// See [RFC 7523, section 3](https://tools.ietf.org/html/rfc7523#section-3).
+    fn assertion_claims(&self) -> Value {
+        let iat = jwt::now();
+        json!({
+            "iss": self.client_id,
+            "sub": self.client_id,
+            "aud": self.provider.token_uri().as_str(),
+            "iat": iat,
+            "exp": iat + ASSERTION_LIFETIME,
+            "jti": random_token(16),
+        })
+    }
+}
+
+fn append_assertion(body: &mut Serializer<String>, client_id: &str, assertion: &str) {
+    body.append_pair("client_id", client_id);
+    body.append_pair("client_assertion_type", JWT_BEARER_ASSERTION_TYPE);
+    body.append_pair("client_assertion", assertion);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ring::hmac;
+    use url::form_urlencoded;
+
+    use crate::client::http_client::AUTHORIZATION;
+    use crate::client::test_util::{MockHttp, Test};
+    use crate::jwt::{test_keys, JwkSet, PrivateKey, SigningKeys};
+
+    fn client(auth: ClientAuth) -> Client<Test> {
+        Client::new(Test::new(), String::from("foo"), String::from("bar"), None).with_auth(auth)
+    }
+
+    fn form(request: &HttpRequest) -> Vec<(String, String)> {
+        form_urlencoded::parse(request.body.as_bytes()).into_owned().collect()
+    }
+
+    fn param<'a>(form: &'a [(String, String)], name: &str) -> Option<&'a str> {
+        form.iter().find(|(k, _)| k == name).map(|(_, v)| &v[..])
+    }
+
+    fn claims(assertion: &str) -> Value {
+        let payload = assertion.split('.').nth(1).unwrap();
+        serde_json::from_slice(&base64::decode_config(payload, base64::URL_SAFE_NO_PAD).unwrap())
+            .unwrap()
+    }
+
+    async fn token_request(auth: ClientAuth) -> HttpRequest {
+        let http = MockHttp::new(&[r#"{"token_type":"Bearer","access_token":"aaaaaaaa"}"#]);
+        client(auth).request_client_credentials_token(&http, None).await.unwrap();
+        http.sent().remove(0)
+    }
+
+    #[tokio::test]
+    async fn client_secret_basic() {
+        let request = token_request(ClientAuth::ClientSecretBasic).await;
+        assert_eq!(
+            vec![
+                (ACCEPT, String::from("application/json")),
+                (AUTHORIZATION, String::from("Basic Zm9vOmJhcg==")),
+                ("Content-Type", String::from("application/x-www-form-urlencoded")),
+            ],
+            request.headers
+        );
+        assert_eq!("grant_type=client_credentials", request.body);
+    }
+
+    #[tokio::test]
+    async fn client_secret_post() {
+        let request = token_request(ClientAuth::ClientSecretPost).await;
+        assert!(request.headers.iter().all(|(name, _)| *name != AUTHORIZATION));
+        assert_eq!("grant_type=client_credentials&client_id=foo&client_secret=bar", request.body);
+    }
+
+    #[tokio::test]
+    async fn none() {
+        let request = token_request(ClientAuth::None).await;
+        assert!(request.headers.iter().all(|(name, _)| *name != AUTHORIZATION));
+        assert_eq!("grant_type=client_credentials&client_id=foo", request.body);
+    }
+
+    #[tokio::test]
+    async fn client_secret_jwt() {
+        let request = token_request(ClientAuth::ClientSecretJwt).await;
+        let form = form(&request);
+        assert_eq!(Some(JWT_BEARER_ASSERTION_TYPE), param(&form, "client_assertion_type"));
+        assert_eq!(None, param(&form, "client_secret"));
+
+        let assertion = param(&form, "client_assertion").unwrap();
+        let (input, signature) = assertion.rsplit_once('.').unwrap();
+        let key = hmac::Key::new(hmac::HMAC_SHA256, b"bar");
+        let signature = base64::decode_config(signature, base64::URL_SAFE_NO_PAD).unwrap();
+        hmac::verify(&key, input.as_bytes(), &signature).unwrap();
+        assert_eq!("HS256", jwt::decode_header(assertion).unwrap()["alg"]);
+    }
+
+    #[tokio::test]
+    async fn private_key_jwt() {
+        let key = PrivateKey::from_pem(test_keys::EC_P256_PKCS8).unwrap().with_key_id("k1");
+        let keys = JwkSet { keys: vec![key.public_jwk()] };
+        let request = token_request(ClientAuth::PrivateKeyJwt(Arc::new(key))).await;
+        assert!(request.headers.iter().all(|(name, _)| *name != AUTHORIZATION));
+
+        let form = form(&request);
+        assert_eq!(Some("foo"), param(&form, "client_id"));
+        let assertion = param(&form, "client_assertion").unwrap();
+        assert_eq!(claims(assertion), jwt::decode(assertion, &keys).unwrap());
+
+        let claims = claims(assertion);
+        assert_eq!("foo", claims["iss"]);
+        assert_eq!("foo", claims["sub"]);
+        assert_eq!("http://example.com/oauth2/token", claims["aud"]);
+        assert_eq!(
+            ASSERTION_LIFETIME,
+            claims["exp"].as_u64().unwrap() - claims["iat"].as_u64().unwrap()
+        );
+        assert!(claims["jti"].is_string());
+    }
+
+    #[tokio::test]
+    async fn private_key_jwt_rotation() {
+        let old = PrivateKey::from_pem(test_keys::EC_P256_PKCS8).unwrap().with_key_id("k1");
+        let new = PrivateKey::from_pem(test_keys::RSA_PKCS8).unwrap().with_key_id("k2");
+        let keys = SigningKeys::new(old).with_key(new);
+        let jwks = keys.jwks();
+        let keys = keys.with_current("k2").unwrap();
+
+        let request = token_request(ClientAuth::private_key_jwt(keys)).await;
+        let form = form(&request);
+        let assertion = param(&form, "client_assertion").unwrap();
+        let header = jwt::decode_header(assertion).unwrap();
+        assert_eq!("k2", header["kid"]);
+        assert_eq!("RS256", header["alg"]);
+        assert_eq!("foo", jwt::decode(assertion, &jwks).unwrap()["sub"]);
+    }
+
+    #[test]
+    fn method() {
+        for method in &["client_secret_basic", "client_secret_post", "none", "client_secret_jwt"] {
+            assert_eq!(*method, ClientAuth::from_method(method).unwrap().method());
+        }
+        assert!(ClientAuth::from_method("private_key_jwt").is_none());
+    }
+}
@@ -0,0 +1,366 @@
+//! Authorization response handling.
+//!
+//! See [RFC 6749, section 4.1.2](http://tools.ietf.org/html/rfc6749#section-4.1.2).
+
+use serde_json::{Map, Value};
+use url::{form_urlencoded, Url};
+
+use crate::client::response::{FromResponse, ParseError};
+use crate::client::{Client, ClientError};
+use crate::error::OAuth2Error;
+use crate::provider::Provider;
+use crate::util::constant_time_eq;
+
+/// Authorization response parameters which must not be repeated.
+///
+/// See [RFC 6749, section 3.1](http://tools.ietf.org/html/rfc6749#section-3.1).
+const SINGLE_PARAMS: &[&str] = &["code", "state", "iss", "error", "error_description", "error_uri"];
+
+/// A successful authorization response, received at the redirect URI.
+///
+/// See [RFC 6749, section 4.1.2](http://tools.ietf.org/html/rfc6749#section-4.1.2).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuthorizationResponse {
+    /// The authorization code.
+    pub code: String,
+
+    /// The `state` parameter, if the authorization request had one.
+    pub state: Option<String>,
+
+    /// Remaining parameters, such as `iss` or `session_state`.
+    pub extra: Vec<(String, String)>,
+}
+
+impl AuthorizationResponse {
+    /// Parses the parameters of a redirect URI.
+    ///
+    /// The parameters are taken from the query, or from the fragment if the query has neither
+    /// `code` nor `error`, as with `response_mode=fragment`.
+    ///
+    /// This does not check the `state` or `iss` parameters; prefer [`Client::handle_callback`],
+    /// which checks them before reporting error responses.
+    pub fn from_url(url: &Url) -> Result<Self, ClientError> {
+        ResponseParams::from_url(url)?.into_response()
+    }
+
+    /// Parses `application/x-www-form-urlencoded` parameters: a query string, a fragment, or a
+    /// `response_mode=form_post` request body.
+    ///
+    /// Error responses are `ClientError::OAuth2` errors. Repeated `code`, `state`, `iss` or error
+    /// parameters are rejected.
+    ///
+    /// See [RFC 6749, section 4.1.2.1](http://tools.ietf.org/html/rfc6749#section-4.1.2.1).
+    pub fn from_params(params: &str) -> Result<Self, ClientError> {
+        ResponseParams::parse(params)?.into_response()
+    }
+
+    /// Returns the first remaining parameter with the given name.
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.extra.iter().find(|(k, _)| k == name).map(|(_, v)| &v[..])
+    }
+
+    /// Checks that the `state` parameter equals the one sent, in constant time.
+    ///
+    /// See [RFC 6749, section 10.12](http://tools.ietf.org/html/rfc6749#section-10.12).
+    pub fn verify_state(&self, expected_state: &str) -> Result<(), ClientError> {
+        verify_state(self.state.as_deref(), expected_state)
+    }
+}
+
+/// The parameters of a successful or error authorization response.
+pub(crate) struct ResponseParams {
+    code: Option<String>,
+    pub(crate) state: Option<String>,
+    error: Map<String, Value>,
+    extra: Vec<(String, String)>,
+}
+
+impl ResponseParams {
+    pub(crate) fn from_url(url: &Url) -> Result<Self, ParseError> {
+        let in_query = url.query_pairs().any(|(k, _)| k == "code" || k == "error");
+        match (in_query, url.fragment()) {
+            (false, Some(fragment)) => ResponseParams::parse(fragment),
+            _ => ResponseParams::parse(url.query().unwrap_or("")),
+        }
+    }
+
+    fn parse(params: &str) -> Result<Self, ParseError> {
+        let mut code = None;
+        let mut state = None;
+        let mut error = Map::new();
+        let mut extra = Vec::new();
+        let mut seen = Vec::new();
+
+        for (key, value) in form_urlencoded::parse(params.as_bytes()).into_owned() {
+            if let Some(&name) = SINGLE_PARAMS.iter().find(|&&name| name == key) {
+                if seen.contains(&name) {
+                    return Err(ParseError::DuplicateField(name));
+                }
+                seen.push(name);
+            }
+            match &key[..] {
+                "code" => code = Some(value),
+                "state" => state = Some(value),
+                "error" | "error_description" | "error_uri" => {
+                    error.insert(key, Value::String(value));
+                }
+                _ => extra.push((key, value)),
+            }
+        }
+
+        Ok(ResponseParams { code, state, error, extra })
+    }
+
+    /// Returns true if the parameters are an authorization response, with a code or an error.
+    #[cfg(feature = "loopback")]
+    pub(crate) fn is_response(&self) -> bool {
+        self.code.is_some() || self.error.contains_key("error")
+    }
+
+    fn iss(&self) -> Option<&str> {
+        self.extra.iter().find(|(k, _)| k == "iss").map(|(_, v)| &v[..])
+    }
+
+    pub(crate) fn into_response(self) -> Result<AuthorizationResponse, ClientError> {
+        if self.error.contains_key("error") {
+            return Err(OAuth2Error::from_response(&Value::Object(self.error))?.into());
+        }
+        let code = self.code.ok_or(ParseError::ExpectedFieldType("code", "string"))?;
+        Ok(AuthorizationResponse { code, state: self.state, extra: self.extra })
+    }
+}
+
+pub(crate) fn verify_state(state: Option<&str>, expected_state: &str) -> Result<(), ClientError> {
+    match state {
+        Some(state) if constant_time_eq(state.as_bytes(), expected_state.as_bytes()) => Ok(()),
+        _ => Err(ClientError::StateMismatch),
+    }
+}
+
+impl<P: Provider> Client<P> {
+    /// Handles a request to the redirect URI, returning the authorization code on success.
+    ///
+    /// The `state` parameter must equal `expected_state`, the one sent in the authorization
+    /// request, and the `iss` parameter is checked with [`Client::verify_issuer`]. Both are checked
+    /// before error responses are returned as `ClientError::OAuth2` errors, so that errors cannot
+    /// be injected. See [`AuthorizationResponse::from_url`] for where parameters are taken from,
+    /// and to parse responses to requests without a state.
+    pub fn handle_callback(
+        &self,
+        url: &Url,
+        expected_state: &str,
+    ) -> Result<AuthorizationResponse, ClientError> {
+        self.check_callback(ResponseParams::from_url(url)?, expected_state)
+    }
+
+    /// Handles a `response_mode=form_post` request body, returning the authorization code on
+    /// success.
+    ///
+    /// See [OAuth 2.0 Form Post Response Mode](https://openid.net/specs/oauth-v2-form-post-response-mode-1_0.html).
+    pub fn handle_form_post(
+        &self,
+        body: &str,
+        expected_state: &str,
+    ) -> Result<AuthorizationResponse, ClientError> {
+        self.check_callback(ResponseParams::parse(body)?, expected_state)
+    }
+
+    /// Checks the `iss` parameter of an authorization response against the provider's issuer
+    /// identifier, defending against mix-up attacks.
+    ///
+    /// The parameter is required if the provider supports it, and otherwise checked only when
+    /// present. Providers with no known issuer identifier are not checked. Fails with
+    /// `ClientError::MixUp`.
+    ///
+    /// See [RFC 9207, section 2.4](https://tools.ietf.org/html/rfc9207#section-2.4).
+    pub fn verify_issuer(&self, response: &AuthorizationResponse) -> Result<(), ClientError> {
+        self.verify_iss(response.param("iss"))
+    }
+
+    fn verify_iss(&self, iss: Option<&str>) -> Result<(), ClientError> {
+        let expected = match self.provider.issuer() {
+            Some(expected) => expected,
+            None => return Ok(()),
+        };
+        match iss {
+            Some(found) if found == expected => Ok(()),
+            None if !self.provider.authorization_response_iss_parameter_supported() => Ok(()),
+            found => Err(ClientError::MixUp {
+                expected: expected.to_owned(),
+                found: found.map(str::to_owned),
+            }),
+        }
+    }
+
+    /// Checks the `state` and `iss` parameters of successful and error responses alike.
+    fn check_callback(
+        &self,
+        params: ResponseParams,
+        expected_state: &str,
+    ) -> Result<AuthorizationResponse, ClientError> {
+        verify_state(params.state.as_deref(), expected_state)?;
+        self.verify_iss(params.iss())?;
+        params.into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::client::test_util::Test;
+    use crate::error::OAuth2ErrorCode;
+
+    fn client() -> Client<Test> {
+        Client::new(Test::new(), String::from("foo"), String::from("bar"), None)
+    }
+
+    fn url(s: &str) -> Url {
+        Url::parse(s).unwrap()
+    }
+
+    #[test]
+    fn handle_callback() {
+        let response = client()
+            .handle_callback(
+                &url("https://client.example.com/cb?code=SplxlOBeZQQYbYS6WxSbIA&state=xyz&iss=x"),
+                "xyz",
+            )
+            .unwrap();
+        assert_eq!("SplxlOBeZQQYbYS6WxSbIA", response.code);
+        assert_eq!(Some("xyz"), response.state.as_ref().map(|s| &s[..]));
+        assert_eq!(Some("x"), response.param("iss"));
+    }
+
+    #[test]
+    fn handle_callback_fragment() {
+        let response = client()
+            .handle_callback(&url("https://client.example.com/cb?a=b#code=abc&state=xyz"), "xyz")
+            .unwrap();
+        assert_eq!("abc", response.code);
+    }
+
+    #[test]
+    fn handle_callback_state_mismatch() {
+        let client = client();
+        let uri = url("https://client.example.com/cb?code=abc&state=xyz");
+        assert!(matches!(
+            client.handle_callback(&uri, "xyw"),
+            Err(ClientError::StateMismatch)
+        ));
+        let uri = url("https://client.example.com/cb?code=abc");
+        assert!(matches!(
+            client.handle_callback(&uri, "xyz"),
+            Err(ClientError::StateMismatch)
+        ));
+        assert!(AuthorizationResponse::from_url(&uri).is_ok());
+    }
+
+    #[test]
+    fn handle_callback_error() {
+        let uri = url(
+            "https://client.example.com/cb?error=access_denied&error_description=User+denied&state=xyz",
+        );
+        match client().handle_callback(&uri, "xyz") {
+            Err(ClientError::OAuth2(err)) => {
+                assert_eq!(OAuth2ErrorCode::AccessDenied, err.code);
+                assert_eq!(Some("User denied"), err.description.as_ref().map(|s| &s[..]));
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn handle_callback_missing_code() {
+        assert!(matches!(
+            client().handle_callback(&url("https://client.example.com/cb?state=xyz"), "xyz"),
+            Err(ClientError::Parse(ParseError::ExpectedFieldType("code", _)))
+        ));
+    }
+
+    #[test]
+    fn handle_callback_issuer() {
+        let client = Client::new(
+            Test { issuer: Some(String::from("https://as.example.com")), ..Test::new() },
+            String::from("foo"),
+            String::from("bar"),
+            None,
+        );
+        let ok = url(
+            "https://client.example.com/cb?code=abc&state=xyz&iss=https%3A%2F%2Fas.example.com",
+        );
+        let other = url(
+            "https://client.example.com/cb?code=abc&state=xyz&iss=https%3A%2F%2Fevil.example",
+        );
+        let missing = url("https://client.example.com/cb?code=abc&state=xyz");
+
+        assert!(client.handle_callback(&ok, "xyz").is_ok());
+        assert!(client.handle_callback(&missing, "xyz").is_ok());
+        match client.handle_callback(&other, "xyz") {
+            Err(ClientError::MixUp { expected, found }) => {
+                assert_eq!("https://as.example.com", expected);
+                assert_eq!(Some("https://evil.example"), found.as_deref());
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+
+        let provider = Test { iss_parameter_supported: true, ..client.provider };
+        let client = Client { provider, ..client };
+        assert!(client.handle_callback(&ok, "xyz").is_ok());
+        assert!(matches!(
+            client.handle_callback(&missing, "xyz"),
+            Err(ClientError::MixUp { found: None, .. })
+        ));
+    }
+
+    #[test]
+    fn handle_callback_error_checked() {
+        let client = Client::new(
+            Test { issuer: Some(String::from("https://as.example.com")), ..Test::new() },
+            String::from("foo"),
+            String::from("bar"),
+            None,
+        );
+        let injected = url(
+            "https://client.example.com/cb?error=access_denied&state=xyz&iss=https%3A%2F%2Fevil.example",
+        );
+        assert!(matches!(
+            client.handle_callback(&injected, "xyz"),
+            Err(ClientError::MixUp { .. })
+        ));
+        let forged = url("https://client.example.com/cb?error=access_denied&state=xyw");
+        assert!(matches!(
+            client.handle_callback(&forged, "xyz"),
+            Err(ClientError::StateMismatch)
+        ));
+        assert!(matches!(
+            client.handle_form_post("error=access_denied", "xyz"),
+            Err(ClientError::StateMismatch)
+        ));
+    }
+
+    #[test]
+    fn handle_callback_duplicate() {
+        let client = client();
+        for (query, name) in &[
+            ("code=abc&code=def", "code"),
+            ("code=abc&state=xyz&state=xyz", "state"),
+            ("iss=x&code=abc&iss=y", "iss"),
+            ("error=access_denied&error=invalid_request", "error"),
+        ] {
+            let uri = url(&format!("https://client.example.com/cb?{}", query));
+            match client.handle_callback(&uri, "xyz") {
+                Err(ClientError::Parse(ParseError::DuplicateField(found))) => {
+                    assert_eq!(*name, found)
+                }
+                other => panic!("unexpected result {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn handle_form_post() {
+        let response = client().handle_form_post("code=abc&state=x%2By", "x+y").unwrap();
+        assert_eq!("abc", response.code);
+    }
+}
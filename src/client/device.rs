@@ -0,0 +1,364 @@
+//! Device authorization grant.
+//!
+//! See [RFC 8628](https://tools.ietf.org/html/rfc8628).
+
+use std::future::Future;
+use std::time::{Duration, SystemTime};
+
+use serde_json::Value;
+use url::form_urlencoded::Serializer;
+
+use crate::client::response::{FromResponse, ParseError};
+use crate::client::{Client, ClientError, GrantParams, HttpClient};
+use crate::error::{OAuth2Error, OAuth2ErrorCode};
+use crate::provider::Provider;
+
+/// Polling interval used when the server does not specify one.
+///
+/// See [RFC 8628, section 3.2](https://tools.ietf.org/html/rfc8628#section-3.2).
+const DEFAULT_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Amount by which the polling interval increases on a `slow_down` error.
+///
+/// See [RFC 8628, section 3.5](https://tools.ietf.org/html/rfc8628#section-3.5).
+const SLOW_DOWN_INCREMENT: Duration = Duration::from_secs(5);
+
+/// Device authorization response.
+///
+/// See [RFC 8628, section 3.2](https://tools.ietf.org/html/rfc8628#section-3.2).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeviceAuthorization {
+    device_code: String,
+    user_code: String,
+    verification_uri: String,
+    verification_uri_complete: Option<String>,
+    expires: SystemTime,
+    interval: Duration,
+}
+
+impl DeviceAuthorization {
+    /// Returns the device verification code.
+    pub fn device_code(&self) -> &str { &self.device_code }
+
+    /// Returns the end-user verification code, to be displayed to the user.
+    pub fn user_code(&self) -> &str { &self.user_code }
+
+    /// Returns the end-user verification URI, where the user enters the user code.
+    pub fn verification_uri(&self) -> &str { &self.verification_uri }
+
+    /// Returns the verification URI including the user code, if available.
+    pub fn verification_uri_complete(&self) -> Option<&str> {
+        self.verification_uri_complete.as_ref().map(|s| &s[..])
+    }
+
+    /// Returns the expiry time of the device and user codes.
+    pub fn expires(&self) -> SystemTime { self.expires }
+
+    /// Returns the minimum amount of time to wait between polling requests.
+    pub fn interval(&self) -> Duration { self.interval }
+}
+
+impl FromResponse for DeviceAuthorization {
+    fn from_response(json: &Value) -> Result<Self, ParseError> {
+        let obj = json.as_object().ok_or(ParseError::ExpectedType("object"))?;
+
+        let device_code = obj.get("device_code")
+            .and_then(Value::as_str)
+            .ok_or(ParseError::ExpectedFieldType("device_code", "string"))?;
+        let user_code = obj.get("user_code")
+            .and_then(Value::as_str)
+            .ok_or(ParseError::ExpectedFieldType("user_code", "string"))?;
+        // Google uses `verification_url`.
+        let verification_uri = obj.get("verification_uri")
+            .or_else(|| obj.get("verification_url"))
+            .and_then(Value::as_str)
+            .ok_or(ParseError::ExpectedFieldType("verification_uri", "string"))?;
+        let verification_uri_complete = obj.get("verification_uri_complete")
+            .and_then(Value::as_str);
+        let expires_in = obj.get("expires_in")
+            .and_then(Value::as_i64)
+            .ok_or(ParseError::ExpectedFieldType("expires_in", "i64"))?;
+        let interval = obj.get("interval")
+            .and_then(Value::as_u64)
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_INTERVAL);
+
+        Ok(DeviceAuthorization {
+            device_code: device_code.into(),
+            user_code: user_code.into(),
+            verification_uri: verification_uri.into(),
+            verification_uri_complete: verification_uri_complete.map(Into::into),
+            expires: SystemTime::now() + Duration::from_secs(expires_in.try_into().unwrap_or(0)),
+            interval,
+        })
+    }
+}
+
+impl<P: Provider> Client<P> {
+    /// Requests device and user codes from the provider's device authorization endpoint.
+    ///
+    /// See [RFC 8628, section 3.1](https://tools.ietf.org/html/rfc8628#section-3.1).
+    pub async fn request_device_authorization(
+        &self,
+        http_client: &impl HttpClient,
+        scope: Option<&str>,
+    ) -> Result<DeviceAuthorization, ClientError> {
+        let params = GrantParams { scope, ..GrantParams::default() };
+        self.request_device_authorization_with(http_client, &params).await
+    }
+
+    /// Requests device and user codes with additional parameters, such as resource indicators.
+    ///
+    /// See [RFC 8707, section 2.1](https://tools.ietf.org/html/rfc8707#section-2.1).
+    pub async fn request_device_authorization_with(
+        &self,
+        http_client: &impl HttpClient,
+        params: &GrantParams<'_>,
+    ) -> Result<DeviceAuthorization, ClientError> {
+        let uri = self.provider.device_authorization_uri()
+            .ok_or(ClientError::MissingEndpoint("device authorization"))?;
+
+        let body = {
+            // Serializer can't go across await points. See https://github.com/servo/rust-url/pull/550
+            let mut body = Serializer::new(String::new());
+            if self.needs_client_id() {
+                body.append_pair("client_id", &self.client_id);
+            }
+            params.append_to(&mut body);
+            body.finish()
+        };
+
+        let json = self.post_endpoint(http_client, uri, body).await?;
+        let authorization = DeviceAuthorization::from_response(&json)?;
+        Ok(authorization)
+    }
+
+    /// Makes a single device access token request.
+    ///
+    /// Fails with `AuthorizationPending` or `SlowDown` OAuth 2.0 errors while the user has not
+    /// yet completed authorization. See [`Client::poll_device_token`] for a polling loop.
+    ///
+    /// See [RFC 8628, section 3.4](https://tools.ietf.org/html/rfc8628#section-3.4).
+    pub async fn request_device_token(
+        &self,
+        http_client: &impl HttpClient,
+        device: &DeviceAuthorization,
+    ) -> Result<P::Token, ClientError> {
+        let body = {
+            // Serializer can't go across await points. See https://github.com/servo/rust-url/pull/550
+            let mut body = Serializer::new(String::new());
+            body.append_pair("grant_type", "urn:ietf:params:oauth:grant-type:device_code");
+            body.append_pair("device_code", &device.device_code);
+            if self.needs_client_id() {
+                body.append_pair("client_id", &self.client_id);
+            }
+            body.finish()
+        };
+
+        let json = self.post_token(http_client, body).await?;
+        let token = P::Token::from_response(&json)?;
+        Ok(token)
+    }
+
+    /// Polls the token endpoint until the user completes or denies authorization, or the device
+    /// code expires.
+    ///
+    /// `sleep` is called to wait between requests, which keeps this independent of the async
+    /// runtime; with Tokio, pass `tokio::time::sleep`. The interval is increased as requested by
+    /// `slow_down` errors.
+    ///
+    /// See [RFC 8628, section 3.5](https://tools.ietf.org/html/rfc8628#section-3.5).
+    pub async fn poll_device_token<S, F>(
+        &self,
+        http_client: &impl HttpClient,
+        device: &DeviceAuthorization,
+        sleep: S,
+    ) -> Result<P::Token, ClientError>
+    where
+        S: Fn(Duration) -> F,
+        F: Future<Output = ()>,
+    {
+        let mut interval = device.interval;
+        loop {
+            sleep(interval).await;
+
+            // Checked after sleeping, so that no request is made with an expired device code.
+            if SystemTime::now() > device.expires {
+                return Err(ClientError::OAuth2(OAuth2Error {
+                    code: OAuth2ErrorCode::ExpiredToken,
+                    description: Some(String::from("device code expired")),
+                    uri: None,
+                }));
+            }
+
+            match self.request_device_token(http_client, device).await {
+                Err(ClientError::OAuth2(ref err))
+                    if err.code == OAuth2ErrorCode::AuthorizationPending => {}
+                Err(ClientError::OAuth2(ref err)) if err.code == OAuth2ErrorCode::SlowDown => {
+                    interval += SLOW_DOWN_INCREMENT;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+    use url::form_urlencoded;
+
+    use crate::client::test_util::{MockHttp, Test};
+    use crate::client::ClientAuth;
+    use crate::jwt::{test_keys, PrivateKey};
+    use crate::token::Token;
+
+    fn client() -> Client<Test> {
+        Client::new(Test::full(), String::from("foo"), String::from("bar"), None)
+    }
+
+    const AUTHORIZATION: &str = r#"
+        {
+            "device_code":"GmRhmhcxhwAzkoEqiMEg_DnyEysNkuNhszIySk9eS",
+            "user_code":"WDJB-MJHT",
+            "verification_uri":"https://example.com/device",
+            "verification_uri_complete":"https://example.com/device?user_code=WDJB-MJHT",
+            "expires_in":1800,
+            "interval":5
+        }
+    "#;
+
+    #[test]
+    fn from_response() {
+        let device = DeviceAuthorization::from_response(&AUTHORIZATION.parse().unwrap()).unwrap();
+        assert_eq!("WDJB-MJHT", device.user_code());
+        assert_eq!("https://example.com/device", device.verification_uri());
+        assert_eq!(
+            Some("https://example.com/device?user_code=WDJB-MJHT"),
+            device.verification_uri_complete()
+        );
+        assert_eq!(Duration::from_secs(5), device.interval());
+        assert!(device.expires() > SystemTime::now());
+    }
+
+    #[test]
+    fn from_response_default_interval() {
+        let json = r#"
+            {
+                "device_code":"aaaa",
+                "user_code":"bbbb",
+                "verification_url":"https://example.com/device",
+                "expires_in":1800
+            }
+        "#.parse().unwrap();
+        let device = DeviceAuthorization::from_response(&json).unwrap();
+        assert_eq!("https://example.com/device", device.verification_uri());
+        assert_eq!(DEFAULT_INTERVAL, device.interval());
+    }
+
+    #[tokio::test]
+    async fn request_device_authorization() {
+        let http = MockHttp::new(&[AUTHORIZATION]);
+        let device = client().request_device_authorization(&http, Some("baz")).await.unwrap();
+        assert_eq!("WDJB-MJHT", device.user_code());
+        assert_eq!(vec!["http://example.com/oauth2/device"], http.urls());
+        assert_eq!(vec!["client_id=foo&scope=baz"], http.requests());
+    }
+
+    #[tokio::test]
+    async fn request_device_authorization_client_auth() {
+        let key = PrivateKey::from_pem(test_keys::EC_P256_PKCS8).unwrap();
+        let methods = vec![
+            ClientAuth::ClientSecretBasic,
+            ClientAuth::ClientSecretPost,
+            ClientAuth::None,
+            ClientAuth::ClientSecretJwt,
+            ClientAuth::private_key_jwt(key),
+        ];
+        let device = DeviceAuthorization::from_response(&AUTHORIZATION.parse().unwrap()).unwrap();
+        for auth in methods {
+            let http = MockHttp::new(&[
+                AUTHORIZATION,
+                r#"{"token_type":"Bearer","access_token":"aaaaaaaa"}"#,
+            ]);
+            let client = client().with_auth(auth.clone());
+            client.request_device_authorization(&http, None).await.unwrap();
+            client.request_device_token(&http, &device).await.unwrap();
+
+            for body in http.requests() {
+                let client_ids = form_urlencoded::parse(body.as_bytes())
+                    .filter(|(name, _)| name == "client_id")
+                    .count();
+                assert_eq!(1, client_ids, "{:?}: {}", auth, body);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn request_device_authorization_unsupported() {
+        let client = Client::new(Test::new(), String::from("foo"), String::from("bar"), None);
+        let http = MockHttp::new(&[AUTHORIZATION]);
+        assert!(matches!(
+            client.request_device_authorization(&http, None).await,
+            Err(ClientError::MissingEndpoint(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn poll_device_token() {
+        let device = DeviceAuthorization::from_response(&AUTHORIZATION.parse().unwrap()).unwrap();
+        let http = MockHttp::new(&[
+            r#"{"error":"authorization_pending"}"#,
+            r#"{"error":"slow_down"}"#,
+            r#"{"error":"authorization_pending"}"#,
+            r#"{"token_type":"Bearer","access_token":"aaaaaaaa"}"#,
+        ]);
+        let sleeps = Mutex::new(Vec::new());
+        let token = client()
+            .poll_device_token(&http, &device, |d| {
+                sleeps.lock().unwrap().push(d.as_secs());
+                async {}
+            })
+            .await
+            .unwrap();
+        assert_eq!("aaaaaaaa", token.access_token());
+        assert_eq!(vec![5, 5, 10, 10], *sleeps.lock().unwrap());
+        assert_eq!(
+            "grant_type=urn%3Aietf%3Aparams%3Aoauth%3Agrant-type%3Adevice_code&device_code=GmRhmhcxhwAzkoEqiMEg_DnyEysNkuNhszIySk9eS&client_id=foo",
+            http.requests()[0]
+        );
+    }
+
+    #[tokio::test]
+    async fn poll_device_token_denied() {
+        let device = DeviceAuthorization::from_response(&AUTHORIZATION.parse().unwrap()).unwrap();
+        let http = MockHttp::new(&[
+            r#"{"error":"authorization_pending"}"#,
+            r#"{"error":"access_denied"}"#,
+        ]);
+        let result = client().poll_device_token(&http, &device, |_| async {}).await;
+        assert!(matches!(
+            result,
+            Err(ClientError::OAuth2(OAuth2Error { code: OAuth2ErrorCode::AccessDenied, .. }))
+        ));
+    }
+
+    #[tokio::test]
+    async fn poll_device_token_expired() {
+        let mut device =
+            DeviceAuthorization::from_response(&AUTHORIZATION.parse().unwrap()).unwrap();
+        device.expires = SystemTime::now() + Duration::from_millis(10);
+        let http = MockHttp::new(&[r#"{"error":"authorization_pending"}"#]);
+
+        // The device code expires during the first sleep.
+        let result = client()
+            .poll_device_token(&http, &device, |_| tokio::time::sleep(Duration::from_millis(20)))
+            .await;
+        assert!(matches!(
+            result,
+            Err(ClientError::OAuth2(OAuth2Error { code: OAuth2ErrorCode::ExpiredToken, .. }))
+        ));
+        assert!(http.requests().is_empty());
+    }
+}
@@ -3,6 +3,7 @@ use std::{fmt, io};
 
 use crate::client::response::ParseError;
 use crate::error::OAuth2Error;
+use crate::jwt::JwtError;
 
 /// Errors that can occur during authorization.
 #[derive(Debug)]
@@ -37,11 +38,68 @@ pub enum ClientError {
 
     /// OAuth 2.0 error.
     OAuth2(OAuth2Error),
+
+    /// JWT error.
+    Jwt(JwtError),
+
+    /// The provider does not have the named endpoint.
+    MissingEndpoint(&'static str),
+
+    /// The client lacks the named credential, which the request needs.
+    MissingCredential(&'static str),
+
+    /// Unsuccessful HTTP status without an OAuth 2.0 error response.
+    HttpStatus(u16),
+
+    /// The `state` parameter of an authorization response is missing or differs from the one sent.
+    StateMismatch,
+
+    /// A signed `state` parameter is older than the allowed maximum age.
+    StateExpired,
+
+    /// An access token has expired and has no refresh token to renew it with.
+    TokenExpired,
+
+    /// The issuer identifier of discovered metadata differs from the expected one.
+    IssuerMismatch {
+        /// The expected issuer identifier.
+        expected: String,
+
+        /// The issuer identifier received, empty if it was missing.
+        found: String,
+    },
+
+    /// The `iss` parameter of an authorization response is missing or differs from the
+    /// provider's issuer identifier, as in a mix-up attack.
+    MixUp {
+        /// The provider's issuer identifier.
+        expected: String,
+
+        /// The `iss` parameter received, if any.
+        found: Option<String>,
+    },
 }
 
 impl fmt::Display for ClientError {
     fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
-        write!(f, "{}", self.source().unwrap())
+        match *self {
+            ClientError::MissingEndpoint(endpoint) =>
+                write!(f, "Provider has no {} endpoint", endpoint),
+            ClientError::MissingCredential(credential) =>
+                write!(f, "Client has no {}", credential),
+            ClientError::HttpStatus(status) =>
+                write!(f, "Unexpected HTTP status {}", status),
+            ClientError::StateMismatch => write!(f, "State parameter mismatch"),
+            ClientError::StateExpired => write!(f, "State parameter expired"),
+            ClientError::TokenExpired => write!(f, "Token expired and cannot be refreshed"),
+            ClientError::IssuerMismatch { ref expected, ref found } =>
+                write!(f, "Expected issuer {}, found {}", expected, found),
+            ClientError::MixUp { ref expected, found: Some(ref found) } =>
+                write!(f, "Authorization response from issuer {}, expected {}", found, expected),
+            ClientError::MixUp { ref expected, found: None } =>
+                write!(f, "Authorization response without issuer, expected {}", expected),
+            _ => write!(f, "{}", self.source().unwrap()),
+        }
     }
 }
 
@@ -53,6 +111,15 @@ impl Error for ClientError {
             ClientError::Json(ref err) => Some(err),
             ClientError::Parse(ref err) => Some(err),
             ClientError::OAuth2(ref err) => Some(err),
+            ClientError::Jwt(ref err) => Some(err),
+            ClientError::MissingEndpoint(_) => None,
+            ClientError::MissingCredential(_) => None,
+            ClientError::HttpStatus(_) => None,
+            ClientError::StateMismatch => None,
+            ClientError::StateExpired => None,
+            ClientError::TokenExpired => None,
+            ClientError::IssuerMismatch { .. } => None,
+            ClientError::MixUp { .. } => None,
 
             #[cfg(feature = "reqwest-client")]
             ClientError::Reqwest(ref err) => Some(err),
@@ -84,6 +151,7 @@ impl_from!(ClientError::Url, url::ParseError);
 impl_from!(ClientError::Json, serde_json::Error);
 impl_from!(ClientError::Parse, ParseError);
 impl_from!(ClientError::OAuth2, OAuth2Error);
+impl_from!(ClientError::Jwt, JwtError);
 
 #[cfg(feature = "reqwest-client")]
 impl_from!(ClientError::Reqwest, reqwest::Error);
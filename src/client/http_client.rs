@@ -2,58 +2,195 @@
 //!
 //! We provide out-of-the-box implementations for two crates: Hyper, and Reqwest; both of which are
 //! gated by Cargo features. The [`HttpClient`] trait can alternatively be implemented for any other
-//! client type you need.
+//! client type you need, by sending each [`HttpRequest`] as-is.
+
+use serde_json::Value;
+use url::form_urlencoded;
 
 use crate::client::error::ClientError;
 
+/// `Accept` header name.
+pub const ACCEPT: &str = "Accept";
+
+/// `Authorization` header name.
+pub const AUTHORIZATION: &str = "Authorization";
+
+/// `Content-Type` header name.
+pub const CONTENT_TYPE: &str = "Content-Type";
+
+/// `User-Agent` header name.
+pub const USER_AGENT: &str = "User-Agent";
+
+/// HTTP request methods.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Method {
+    /// `GET`
+    Get,
+    /// `POST`
+    Post,
+    /// `PUT`
+    Put,
+    /// `DELETE`
+    Delete,
+}
+
+/// A HTTP request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpRequest {
+    /// Request method.
+    pub method: Method,
+
+    /// Request URL.
+    pub url: String,
+
+    /// Header names and values.
+    pub headers: Vec<(&'static str, String)>,
+
+    /// Request body. Empty for requests without a body.
+    pub body: String,
+}
+
+impl HttpRequest {
+    /// Creates a request without headers or body.
+    pub fn new(method: Method, url: impl Into<String>) -> Self {
+        HttpRequest {
+            method,
+            url: url.into(),
+            headers: Vec::new(),
+            body: String::new(),
+        }
+    }
+
+    /// Adds a header.
+    pub fn header(mut self, name: &'static str, value: impl Into<String>) -> Self {
+        self.headers.push((name, value.into()));
+        self
+    }
+
+    /// Adds HTTP Basic authentication with client credentials, which are form-urlencoded first.
+    ///
+    /// See [RFC 6749, section 2.3.1](http://tools.ietf.org/html/rfc6749#section-2.3.1).
+    pub fn basic_auth(self, client_id: &str, client_secret: &str) -> Self {
+        let encode = |s: &str| form_urlencoded::byte_serialize(s.as_bytes()).collect::<String>();
+        let credentials = format!("{}:{}", encode(client_id), encode(client_secret));
+        let credentials = base64::encode(credentials);
+        self.header(AUTHORIZATION, format!("Basic {}", credentials))
+    }
+
+    /// Adds a bearer access token.
+    ///
+    /// See [RFC 6750, section 2.1](https://tools.ietf.org/html/rfc6750#section-2.1).
+    pub fn bearer_auth(self, access_token: &str) -> Self {
+        self.header(AUTHORIZATION, format!("Bearer {}", access_token))
+    }
+
+    /// Sets an `application/x-www-form-urlencoded` body.
+    pub fn form(mut self, body: String) -> Self {
+        self.body = body;
+        self.header(CONTENT_TYPE, "application/x-www-form-urlencoded")
+    }
+
+    /// Sets an `application/json` body.
+    pub fn json(mut self, body: &Value) -> Self {
+        self.body = body.to_string();
+        self.header(CONTENT_TYPE, "application/json")
+    }
+}
+
+/// A HTTP response.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpResponse {
+    /// Status code.
+    pub status: u16,
+
+    /// Header names and values.
+    pub headers: Vec<(String, String)>,
+
+    /// Response body.
+    pub body: Vec<u8>,
+}
+
+impl HttpResponse {
+    /// Returns the value of the first header with the given name, compared case-insensitively.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| &v[..])
+    }
+
+    /// Returns true if the status code is 2xx.
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    /// Deserializes the body as JSON. An empty body is `null`.
+    pub fn json(&self) -> Result<Value, ClientError> {
+        if self.body.iter().all(u8::is_ascii_whitespace) {
+            return Ok(Value::Null);
+        }
+        Ok(serde_json::from_slice(&self.body)?)
+    }
+}
+
 /// Abstraction of the parts of a HTTP client implementation that this crate needs.
 #[async_trait::async_trait]
-pub trait HttpClient {
-    /// Make a HTTP POST request.
+pub trait HttpClient: Sync {
+    /// Sends a HTTP request.
     ///
-    /// [`client_id`] and [`client_secret`] are to be given as HTTP Basic Auth credentials username
-    /// and password, respectively.
-    ///
-    /// The [`body`] is of content-type `application/x-www-form-urlencoded`, and the response body
-    /// is expected to be `application/json`.
+    /// Non-2xx responses must be returned as responses rather than errors.
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse, ClientError>;
+
+    /// Make a HTTP GET request.
     ///
-    /// The response body must be deserialized into a json value.
-    async fn post(
-        &self,
-        url: &str,
-        client_id: &str,
-        client_secret: &str,
-        body: String,
-    ) -> Result<serde_json::Value, ClientError>;
+    /// The response body is expected to be `application/json`, and must be deserialized into a
+    /// json value. Unsuccessful responses are `ClientError::HttpStatus` errors.
+    async fn get(&self, url: &str) -> Result<Value, ClientError> {
+        let request = HttpRequest::new(Method::Get, url)
+            .header(ACCEPT, "application/json");
+        let response = self.send(request).await?;
+        if !response.is_success() {
+            return Err(ClientError::HttpStatus(response.status));
+        }
+        response.json()
+    }
 }
 
 /// Implementation for Reqwest.
 #[cfg(feature = "reqwest-client")]
 pub mod reqwest_client {
     use super::*;
-    use reqwest::header::{ACCEPT, CONTENT_TYPE};
+    use reqwest::header::HeaderValue;
+    use std::io;
 
     #[async_trait::async_trait]
     impl HttpClient for reqwest::Client {
-        async fn post(
-            &self,
-            url: &str,
-            client_id: &str,
-            client_secret: &str,
-            body: String,
-        ) -> Result<serde_json::Value, ClientError> {
-            let response = reqwest::Client::post(self, url)
-                .basic_auth(client_id, Some(client_secret))
-                .header(ACCEPT, "application/json")
-                .header(CONTENT_TYPE, "application/x-www-form-urlencoded")
-                .body(body)
-                .send()
-                .await?;
-
-            let full = response.bytes().await?;
-            let json = serde_json::from_slice(&full)?;
-
-            Ok(json)
+        async fn send(&self, request: HttpRequest) -> Result<HttpResponse, ClientError> {
+            let method = match request.method {
+                Method::Get => reqwest::Method::GET,
+                Method::Post => reqwest::Method::POST,
+                Method::Put => reqwest::Method::PUT,
+                Method::Delete => reqwest::Method::DELETE,
+            };
+
+            let mut builder = reqwest::Client::request(self, method, &request.url);
+            for (name, value) in request.headers {
+                let mut value = HeaderValue::from_str(&value)
+                    .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+                value.set_sensitive(name.eq_ignore_ascii_case(AUTHORIZATION));
+                builder = builder.header(name, value);
+            }
+            if !request.body.is_empty() {
+                builder = builder.body(request.body);
+            }
+
+            let response = builder.send().await?;
+            let status = response.status().as_u16();
+            let headers = response.headers().iter()
+                .filter_map(|(k, v)| Some((k.as_str().to_owned(), v.to_str().ok()?.to_owned())))
+                .collect();
+            let body = response.bytes().await?.to_vec();
+
+            Ok(HttpResponse { status, headers, body })
         }
     }
 }
@@ -62,13 +199,11 @@ pub mod reqwest_client {
 #[cfg(feature = "hyper-client")]
 pub mod hyper_client {
     use super::*;
-    use base64::write::EncoderWriter as Base64Encoder;
     use http_body_util::BodyExt;
     use hyper::body::Body;
-    use hyper::header::{AUTHORIZATION, ACCEPT, CONTENT_TYPE, HeaderValue};
+    use hyper::header::HeaderValue;
     use hyper::Request;
     use hyper_util::client::legacy::connect::Connect;
-    use std::io::Write;
 
     #[async_trait::async_trait]
     impl<C, B> HttpClient for hyper_util::client::legacy::Client<C, B> where
@@ -78,33 +213,70 @@ pub mod hyper_client {
         B::Data: Send,
         B::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
     {
-        async fn post(
-            &self,
-            url: &str,
-            client_id: &str,
-            client_secret: &str,
-            body: String,
-        ) -> Result<serde_json::Value, ClientError> {
-            let mut auth_header = b"Basic ".to_vec();
-            {
-                let mut enc = Base64Encoder::new(&mut auth_header, base64::STANDARD);
-                write!(enc, "{}:{}", client_id, client_secret)?;
-            }
+        async fn send(&self, request: HttpRequest) -> Result<HttpResponse, ClientError> {
+            let method = match request.method {
+                Method::Get => hyper::Method::GET,
+                Method::Post => hyper::Method::POST,
+                Method::Put => hyper::Method::PUT,
+                Method::Delete => hyper::Method::DELETE,
+            };
 
-            let mut auth_header_val = HeaderValue::from_bytes(&auth_header)
-                .expect("invalid header value"); // should never happen for base64 data
-            auth_header_val.set_sensitive(true);
-
-            let req = Request::post(url)
-                .header(AUTHORIZATION, auth_header_val)
-                .header(ACCEPT, "application/json")
-                .header(CONTENT_TYPE, "application/x-www-form-urlencoded")
-                .body(body.into())?;
+            let mut builder = Request::builder().method(method).uri(&request.url);
+            for (name, value) in request.headers {
+                let mut value = HeaderValue::from_str(&value).map_err(hyper::http::Error::from)?;
+                value.set_sensitive(name.eq_ignore_ascii_case(AUTHORIZATION));
+                builder = builder.header(name, value);
+            }
+            let req = builder.body(request.body.into())?;
 
             let response = self.request(req).await?;
-            let full = response.into_body().collect().await?.to_bytes();
-            let json = serde_json::from_slice(&full)?;
-            Ok(json)
+            let status = response.status().as_u16();
+            let headers = response.headers().iter()
+                .filter_map(|(k, v)| Some((k.as_str().to_owned(), v.to_str().ok()?.to_owned())))
+                .collect();
+            let body = response.into_body().collect().await?.to_bytes().to_vec();
+
+            Ok(HttpResponse { status, headers, body })
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn basic_auth() {
+        let request = HttpRequest::new(Method::Post, "http://example.com")
+            .basic_auth("s6BhdRkqt3", "7Fjfp0ZBr1KtDRbnfVdmIw");
+        assert_eq!(
+            vec![(AUTHORIZATION, String::from("Basic czZCaGRSa3F0Mzo3RmpmcDBaQnIxS3REUmJuZlZkbUl3"))],
+            request.headers
+        );
+    }
+
+    #[test]
+    fn basic_auth_encoded() {
+        // a%3Ab:p%40ss+word
+        let request = HttpRequest::new(Method::Post, "http://example.com")
+            .basic_auth("a:b", "p@ss word");
+        assert_eq!(
+            vec![(AUTHORIZATION, String::from("Basic YSUzQWI6cCU0MHNzK3dvcmQ="))],
+            request.headers
+        );
+    }
+
+    #[test]
+    fn response_json() {
+        let mut response = HttpResponse {
+            status: 200,
+            headers: vec![(String::from("content-type"), String::from("application/json"))],
+            body: Vec::new(),
+        };
+        assert_eq!(Value::Null, response.json().unwrap());
+        assert_eq!(Some("application/json"), response.header("Content-Type"));
+
+        response.body = br#"{"a":1}"#.to_vec();
+        assert_eq!(1, response.json().unwrap()["a"]);
+    }
+}
@@ -0,0 +1,257 @@
+//! Token introspection.
+//!
+//! See [RFC 7662](https://tools.ietf.org/html/rfc7662).
+
+use serde_json::{Map, Value};
+use url::form_urlencoded::Serializer;
+
+use crate::client::response::{FromResponse, ParseError};
+use crate::client::{Client, ClientError, HttpClient};
+use crate::provider::Provider;
+
+/// Token introspection response.
+///
+/// Only `active` is required; inactive tokens carry no other information.
+///
+/// See [RFC 7662, section 2.2](https://tools.ietf.org/html/rfc7662#section-2.2).
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct Introspection {
+    /// Whether the token is currently active.
+    pub active: bool,
+
+    /// Scopes associated with the token.
+    pub scope: Option<String>,
+
+    /// Client which requested the token.
+    pub client_id: Option<String>,
+
+    /// Human-readable identifier of the resource owner who authorized the token.
+    pub username: Option<String>,
+
+    /// Type of the token.
+    pub token_type: Option<String>,
+
+    /// Expiry time, in seconds since the Unix epoch.
+    pub exp: Option<i64>,
+
+    /// Issue time, in seconds since the Unix epoch.
+    pub iat: Option<i64>,
+
+    /// Time before which the token is not to be used, in seconds since the Unix epoch.
+    pub nbf: Option<i64>,
+
+    /// Subject of the token, usually a machine-readable identifier of the resource owner.
+    pub sub: Option<String>,
+
+    /// Intended audiences of the token.
+    pub aud: Vec<String>,
+
+    /// Issuer of the token.
+    pub iss: Option<String>,
+
+    /// Identifier of the token.
+    pub jti: Option<String>,
+
+    /// Remaining members of the response.
+    pub extra: Map<String, Value>,
+}
+
+impl FromResponse for Introspection {
+    fn from_response(json: &Value) -> Result<Self, ParseError> {
+        let obj = json.as_object().ok_or(ParseError::ExpectedType("object"))?;
+
+        let mut extra = obj.clone();
+        let mut string = |key: &'static str| -> Result<Option<String>, ParseError> {
+            match extra.remove(key) {
+                None | Some(Value::Null) => Ok(None),
+                Some(Value::String(s)) => Ok(Some(s)),
+                Some(_) => Err(ParseError::ExpectedFieldType(key, "string")),
+            }
+        };
+        let scope = string("scope")?;
+        let client_id = string("client_id")?;
+        let username = string("username")?;
+        let token_type = string("token_type")?;
+        let sub = string("sub")?;
+        let iss = string("iss")?;
+        let jti = string("jti")?;
+
+        let mut int = |key: &'static str| -> Result<Option<i64>, ParseError> {
+            match extra.remove(key) {
+                None | Some(Value::Null) => Ok(None),
+                Some(value) => value.as_i64()
+                    .map(Some)
+                    .ok_or(ParseError::ExpectedFieldType(key, "i64")),
+            }
+        };
+        let exp = int("exp")?;
+        let iat = int("iat")?;
+        let nbf = int("nbf")?;
+
+        let active = extra.remove("active")
+            .as_ref()
+            .and_then(Value::as_bool)
+            .ok_or(ParseError::ExpectedFieldType("active", "bool"))?;
+
+        let aud = match extra.remove("aud") {
+            None | Some(Value::Null) => Vec::new(),
+            Some(Value::String(aud)) => vec![aud],
+            Some(Value::Array(auds)) => auds.into_iter()
+                .map(|aud| match aud {
+                    Value::String(aud) => Ok(aud),
+                    _ => Err(ParseError::ExpectedFieldType("aud", "string or array of strings")),
+                })
+                .collect::<Result<_, _>>()?,
+            Some(_) => return Err(ParseError::ExpectedFieldType("aud", "string or array of strings")),
+        };
+
+        Ok(Introspection {
+            active,
+            scope,
+            client_id,
+            username,
+            token_type,
+            exp,
+            iat,
+            nbf,
+            sub,
+            aud,
+            iss,
+            jti,
+            extra,
+        })
+    }
+}
+
+impl<P: Provider> Client<P> {
+    /// Asks the provider about the state and metadata of a token.
+    ///
+    /// `token_type_hint` is `access_token`, `refresh_token`, or an extension token type.
+    ///
+    /// See [RFC 7662, section 2.1](https://tools.ietf.org/html/rfc7662#section-2.1).
+    pub async fn introspect(
+        &self,
+        http_client: &impl HttpClient,
+        token: &str,
+        token_type_hint: Option<&str>,
+    ) -> Result<Introspection, ClientError> {
+        let uri = self.provider.introspection_uri()
+            .ok_or(ClientError::MissingEndpoint("introspection"))?;
+
+        let body = {
+            // Serializer can't go across await points. See https://github.com/servo/rust-url/pull/550
+            let mut body = Serializer::new(String::new());
+            body.append_pair("token", token);
+            if let Some(token_type_hint) = token_type_hint {
+                body.append_pair("token_type_hint", token_type_hint);
+            }
+            body.finish()
+        };
+
+        let json = self.post_endpoint(http_client, uri, body).await?;
+        let introspection = Introspection::from_response(&json)?;
+        Ok(introspection)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::client::http_client::HttpResponse;
+    use crate::client::test_util::{MockHttp, Test};
+    use crate::error::{OAuth2Error, OAuth2ErrorCode};
+
+    #[test]
+    fn from_response() {
+        let json = r#"
+            {
+                "active":true,
+                "client_id":"l238j323ds-23ij4",
+                "username":"jdoe",
+                "scope":"read write dolphin",
+                "sub":"Z5O3upPC88QrAjx00dis",
+                "aud":"https://protected.example.net/resource",
+                "iss":"https://server.example.com/",
+                "exp":1419356238,
+                "iat":1419350238,
+                "extension_field":"twenty-seven"
+            }
+        "#.parse().unwrap();
+        let introspection = Introspection::from_response(&json).unwrap();
+        assert!(introspection.active);
+        assert_eq!(Some("jdoe"), introspection.username.as_deref());
+        assert_eq!(Some("read write dolphin"), introspection.scope.as_deref());
+        assert_eq!(vec!["https://protected.example.net/resource"], introspection.aud);
+        assert_eq!(Some(1419356238), introspection.exp);
+        assert_eq!(1, introspection.extra.len());
+        assert_eq!("twenty-seven", introspection.extra["extension_field"]);
+    }
+
+    #[test]
+    fn from_response_inactive() {
+        let json = r#"{"active":false}"#.parse().unwrap();
+        assert_eq!(Introspection::default(), Introspection::from_response(&json).unwrap());
+    }
+
+    #[test]
+    fn from_response_without_active() {
+        let json = r#"{"scope":"foo"}"#.parse().unwrap();
+        assert_eq!(
+            ParseError::ExpectedFieldType("active", "bool"),
+            Introspection::from_response(&json).unwrap_err()
+        );
+    }
+
+    #[test]
+    fn from_response_aud_array() {
+        let json = r#"{"active":true,"aud":["a","b"]}"#.parse().unwrap();
+        assert_eq!(vec!["a", "b"], Introspection::from_response(&json).unwrap().aud);
+    }
+
+    #[tokio::test]
+    async fn introspect() {
+        let client = Client::new(Test::full(), String::from("foo"), String::from("bar"), None);
+        let http = MockHttp::new(&[r#"{"active":true,"sub":"alice"}"#]);
+        let introspection = client.introspect(&http, "aaaaaaaa", Some("access_token")).await.unwrap();
+        assert_eq!(Some("alice"), introspection.sub.as_deref());
+        assert_eq!(vec!["http://example.com/oauth2/introspect"], http.urls());
+        assert_eq!(vec!["token=aaaaaaaa&token_type_hint=access_token"], http.requests());
+    }
+
+    #[tokio::test]
+    async fn introspect_error() {
+        let client = Client::new(Test::full(), String::from("foo"), String::from("bar"), None);
+        let http = MockHttp::new(&[r#"{"error":"invalid_client"}"#]);
+        assert!(matches!(
+            client.introspect(&http, "aaaaaaaa", None).await,
+            Err(ClientError::OAuth2(OAuth2Error { code: OAuth2ErrorCode::InvalidClient, .. }))
+        ));
+    }
+
+    #[tokio::test]
+    async fn introspect_http_status() {
+        let client = Client::new(Test::full(), String::from("foo"), String::from("bar"), None);
+        for (status, body) in &[(401, "Unauthorized"), (500, "<html>Server Error</html>")] {
+            let http = MockHttp::with_responses(vec![HttpResponse {
+                status: *status,
+                headers: Vec::new(),
+                body: body.as_bytes().to_vec(),
+            }]);
+            match client.introspect(&http, "aaaaaaaa", None).await {
+                Err(ClientError::HttpStatus(found)) => assert_eq!(*status, found),
+                other => panic!("unexpected result {:?}", other),
+            }
+        }
+
+        let http = MockHttp::with_responses(vec![HttpResponse {
+            status: 401,
+            headers: Vec::new(),
+            body: br#"{"error":"invalid_client"}"#.to_vec(),
+        }]);
+        assert!(matches!(
+            client.introspect(&http, "aaaaaaaa", None).await,
+            Err(ClientError::OAuth2(OAuth2Error { code: OAuth2ErrorCode::InvalidClient, .. }))
+        ));
+    }
+}
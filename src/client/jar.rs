@@ -0,0 +1,274 @@
+//! JWT-secured authorization requests.
+//!
+//! The authorization request parameters are sent as a signed, and optionally encrypted, request
+//! object, either by value in the `request` parameter or by reference in the `request_uri`
+//! parameter.
+//!
+//! See [RFC 9101](https://tools.ietf.org/html/rfc9101).
+
+use serde_json::{Map, Value};
+use url::form_urlencoded::Serializer;
+use url::Url;
+
+use crate::client::response::FromResponse;
+use crate::client::{AuthParams, Client, ClientError, HttpClient, PushedAuthorization};
+use crate::jwt::{self, Jwk, Signer};
+use crate::provider::Provider;
+use crate::util::random_token;
+
+/// Lifetime of request objects, in seconds.
+const REQUEST_OBJECT_LIFETIME: u64 = 5 * 60;
+
+/// Media type of request objects, sent in the `typ` header parameter.
+///
+/// See [RFC 9101, section 4](https://tools.ietf.org/html/rfc9101#section-4).
+const REQUEST_OBJECT_TYPE: &str = "oauth-authz-req+jwt";
+
+impl<P: Provider> Client<P> {
+    /// Creates a request object containing the parameters of [`Client::auth_uri_with`], signed
+    /// by `signer`.
+    ///
+    /// The `iss` claim is the client ID and the `aud` claim the provider's issuer identifier, or
+    /// its authorization endpoint URI if the issuer is unknown. The request object is valid for
+    /// five minutes and has a random `jti`.
+    ///
+    /// See [RFC 9101, section 4](https://tools.ietf.org/html/rfc9101#section-4).
+    pub async fn request_object<S: Signer + ?Sized>(
+        &self,
+        signer: &S,
+        params: &AuthParams<'_>,
+    ) -> Result<String, ClientError> {
+        let iat = jwt::now();
+        let mut claims: Map<String, Value> = self.auth_request_params(params)
+            .into_iter()
+            .map(|(name, value)| (name.to_owned(), Value::String(value)))
+            .collect();
+        let audience = self.provider.issuer()
+            .unwrap_or_else(|| self.provider.auth_uri().as_str());
+        claims.insert("iss".into(), self.client_id.clone().into());
+        claims.insert("aud".into(), audience.into());
+        claims.insert("iat".into(), iat.into());
+        claims.insert("nbf".into(), iat.into());
+        claims.insert("exp".into(), (iat + REQUEST_OBJECT_LIFETIME).into());
+        claims.insert("jti".into(), random_token(16).into());
+        if let Some(details) = params.authorization_details {
+            // Request objects carry authorization details as JSON, not as a string.
+            let details = serde_json::to_value(details).expect("details serialize to JSON");
+            claims.insert("authorization_details".into(), details);
+        }
+        if params.resource.len() > 1 {
+            // Repeated parameters become an array.
+            claims.insert("resource".into(), params.resource.to_vec().into());
+        }
+
+        let claims = Value::Object(claims);
+        let request = jwt::encode_with_type(&claims, signer, REQUEST_OBJECT_TYPE).await?;
+        Ok(request)
+    }
+
+    /// Like [`Client::request_object`], also encrypting the request object to the provider's
+    /// public key with [`jwt::encrypt`].
+    ///
+    /// See [`JwkSet::encryption_key`](crate::jwt::JwkSet::encryption_key) to choose the key from
+    /// the provider's JWK Set.
+    pub async fn encrypted_request_object<S: Signer + ?Sized>(
+        &self,
+        signer: &S,
+        params: &AuthParams<'_>,
+        key: &Jwk,
+    ) -> Result<String, ClientError> {
+        let request = self.request_object(signer, params).await?;
+        let request = jwt::encrypt(request.as_bytes(), Some("JWT"), key)?;
+        Ok(request)
+    }
+
+    /// Constructs an authorization request URI passing a request object by value, with only
+    /// `client_id` and `request`.
+    ///
+    /// See [RFC 9101, section 5.1](https://tools.ietf.org/html/rfc9101#section-5.1).
+    pub fn auth_uri_with_request(&self, request: &str) -> Url {
+        let mut uri = self.provider.auth_uri().clone();
+        uri.query_pairs_mut()
+            .append_pair("client_id", &self.client_id)
+            .append_pair("request", request);
+        uri
+    }
+
+    /// Constructs an authorization request URI passing a request object by reference, with only
+    /// `client_id` and `request_uri`.
+    ///
+    /// The request URI is either hosted by the client or returned by
+    /// [`Client::push_request_object`].
+    ///
+    /// See [RFC 9101, section 5.2](https://tools.ietf.org/html/rfc9101#section-5.2).
+    pub fn auth_uri_with_request_uri(&self, request_uri: &str) -> Url {
+        let mut uri = self.provider.auth_uri().clone();
+        uri.query_pairs_mut()
+            .append_pair("client_id", &self.client_id)
+            .append_pair("request_uri", request_uri);
+        uri
+    }
+
+    /// Constructs an authorization request URI passing the parameters of
+    /// [`Client::auth_uri_with`] in a request object signed by `signer`.
+    ///
+    /// This is the signed alternative to [`Client::auth_uri_with`].
+    pub async fn signed_auth_uri_with<S: Signer + ?Sized>(
+        &self,
+        signer: &S,
+        params: &AuthParams<'_>,
+    ) -> Result<Url, ClientError> {
+        let request = self.request_object(signer, params).await?;
+        Ok(self.auth_uri_with_request(&request))
+    }
+
+    /// Pushes a request object to the provider's pushed authorization request endpoint,
+    /// authenticating the client, to pass it by reference.
+    ///
+    /// See [RFC 9126, section 3](https://tools.ietf.org/html/rfc9126#section-3).
+    pub async fn push_request_object(
+        &self,
+        http_client: &impl HttpClient,
+        request: &str,
+    ) -> Result<PushedAuthorization, ClientError> {
+        let uri = self.provider.pushed_authorization_request_uri()
+            .ok_or(ClientError::MissingEndpoint("pushed authorization request"))?;
+
+        let body = {
+            // Serializer can't go across await points. See https://github.com/servo/rust-url/pull/550
+            let mut body = Serializer::new(String::new());
+            if self.needs_client_id() {
+                body.append_pair("client_id", &self.client_id);
+            }
+            body.append_pair("request", request);
+            body.finish()
+        };
+
+        let json = self.post_endpoint(http_client, uri, body).await?;
+        let pushed = PushedAuthorization::from_response(&json)?;
+        Ok(pushed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::client::test_util::{MockHttp, Test};
+    use crate::client::ClientAuth;
+    use crate::jwt::{test_keys, Algorithm, JwkSet, PrivateKey};
+    use crate::oidc::Nonce;
+
+    fn client() -> Client<Test> {
+        Client::new(
+            Test { issuer: Some(String::from("https://as.example.com")), ..Test::full() },
+            String::from("foo"),
+            String::from("bar"),
+            Some(String::from("https://client.example.com/cb")),
+        )
+    }
+
+    fn header(jwt: &str) -> Value {
+        jwt::decode_header(jwt).unwrap()
+    }
+
+    #[tokio::test]
+    async fn signed_auth_uri_with() {
+        let client = client();
+        let key = PrivateKey::from_pem(test_keys::RSA_PKCS8)
+            .unwrap()
+            .with_algorithm(Algorithm::PS256)
+            .unwrap();
+        let nonce = Nonce::new();
+
+        let uri = client
+            .signed_auth_uri_with(&key, &AuthParams {
+                scope: Some("openid"),
+                state: Some("xyz"),
+                nonce: Some(&nonce),
+                resource: &["https://a.example.com/", "https://b.example.com/"],
+                ..AuthParams::default()
+            })
+            .await
+            .unwrap();
+        let params: Vec<_> = uri.query_pairs().into_owned().collect();
+        assert_eq!(2, params.len());
+        assert_eq!(("client_id".to_owned(), "foo".to_owned()), params[0]);
+        assert_eq!("request", params[1].0);
+
+        let request = &params[1].1;
+        assert_eq!("PS256", header(request)["alg"]);
+        assert_eq!("oauth-authz-req+jwt", header(request)["typ"]);
+
+        let keys = JwkSet { keys: vec![key.public_jwk()] };
+        let claims = jwt::decode(request, &keys).unwrap();
+        assert_eq!("code", claims["response_type"]);
+        assert_eq!("foo", claims["client_id"]);
+        assert_eq!("https://client.example.com/cb", claims["redirect_uri"]);
+        assert_eq!("openid", claims["scope"]);
+        assert_eq!("xyz", claims["state"]);
+        assert_eq!(nonce.as_str(), claims["nonce"]);
+        assert_eq!(
+            serde_json::json!(["https://a.example.com/", "https://b.example.com/"]),
+            claims["resource"]
+        );
+        assert_eq!("foo", claims["iss"]);
+        assert_eq!("https://as.example.com", claims["aud"]);
+        assert_eq!(
+            REQUEST_OBJECT_LIFETIME,
+            claims["exp"].as_u64().unwrap() - claims["iat"].as_u64().unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn encrypted_request_object() {
+        let key = PrivateKey::from_pem(test_keys::EC_P256_PKCS8).unwrap();
+        let encryption_key = Jwk { alg: None, use_: Some(String::from("enc")), ..key.public_jwk() };
+
+        let request = client()
+            .encrypted_request_object(&key, &AuthParams::default(), &encryption_key)
+            .await
+            .unwrap();
+        assert_eq!(5, request.split('.').count());
+        assert_eq!("JWT", header(&request)["cty"]);
+
+        assert!(matches!(
+            client()
+                .encrypted_request_object(&key, &AuthParams::default(), &key.public_jwk())
+                .await,
+            Err(ClientError::Jwt(jwt::JwtError::InvalidKey))
+        ));
+    }
+
+    #[tokio::test]
+    async fn push_request_object() {
+        let http = MockHttp::new(&[r#"{"request_uri":"urn:example:bwc4JK","expires_in":90}"#]);
+        let client = client();
+
+        let pushed = client.push_request_object(&http, "a.b.c").await.unwrap();
+        assert_eq!("client_id=foo&request=a.b.c", http.requests()[0]);
+        assert_eq!(
+            "http://example.com/oauth2/auth?client_id=foo&request_uri=urn%3Aexample%3Abwc4JK",
+            client.auth_uri_with_request_uri(pushed.request_uri()).as_str()
+        );
+    }
+
+    #[tokio::test]
+    async fn push_request_object_client_auth() {
+        let http = MockHttp::new(&[r#"{"request_uri":"urn:example:bwc4JK","expires_in":90}"#]);
+
+        let client = client().with_auth(ClientAuth::ClientSecretPost);
+        client.push_request_object(&http, "a.b.c").await.unwrap();
+        let client = client.with_auth(ClientAuth::None);
+        client.push_request_object(&http, "a.b.c").await.unwrap();
+
+        let client = client.with_auth(ClientAuth::ClientSecretJwt);
+        client.push_request_object(&http, "a.b.c").await.unwrap();
+
+        let requests = http.requests();
+        assert_eq!("request=a.b.c&client_id=foo&client_secret=bar", requests[0]);
+        assert_eq!("request=a.b.c&client_id=foo", requests[1]);
+        assert!(requests[2].starts_with("request=a.b.c&client_id=foo&client_assertion_type="));
+        assert_eq!(1, requests[2].matches("client_id=").count());
+    }
+}
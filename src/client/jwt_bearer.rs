@@ -0,0 +1,138 @@
+//! JWT bearer assertion grant.
+//!
+//! See [RFC 7523, section 2.1](https://tools.ietf.org/html/rfc7523#section-2.1).
+
+use std::time::Duration;
+
+use serde_json::json;
+use url::form_urlencoded::Serializer;
+
+use crate::client::response::FromResponse;
+use crate::client::{Client, ClientError, GrantParams, HttpClient};
+use crate::jwt::{self, Signer};
+use crate::provider::Provider;
+use crate::util::random_token;
+
+/// Claims of a JWT bearer assertion.
+///
+/// See [RFC 7523, section 3](https://tools.ietf.org/html/rfc7523#section-3).
+#[derive(Debug, Clone, Copy)]
+pub struct AssertionParams<'a> {
+    /// The `iss` claim, usually the client ID or service account email.
+    pub issuer: &'a str,
+
+    /// The `sub` claim, the principal the token is requested for.
+    pub subject: Option<&'a str>,
+
+    /// The `aud` claim. Defaults to the provider's token endpoint URI.
+    pub audience: Option<&'a str>,
+
+    /// Time until the assertion expires. Providers typically allow at most one hour.
+    pub lifetime: Duration,
+}
+
+impl<P: Provider> Client<P> {
+    /// Requests an access token using a JWT bearer assertion signed by `signer`.
+    ///
+    /// The assertion has a random `jti` and is valid from now for `assertion.lifetime`. The
+    /// requested scope is sent both as the `scope` claim, as Google requires, and as the `scope`
+    /// request parameter.
+    ///
+    /// See [RFC 7523, section 2.1](https://tools.ietf.org/html/rfc7523#section-2.1).
+    pub async fn request_jwt_bearer_token<S: Signer + ?Sized>(
+        &self,
+        http_client: &impl HttpClient,
+        signer: &S,
+        assertion: &AssertionParams<'_>,
+        params: &GrantParams<'_>,
+    ) -> Result<P::Token, ClientError> {
+        let iat = jwt::now();
+        let mut claims = json!({
+            "iss": assertion.issuer,
+            "aud": assertion.audience.unwrap_or_else(|| self.provider.token_uri().as_str()),
+            "iat": iat,
+            "exp": iat + assertion.lifetime.as_secs(),
+            "jti": random_token(16),
+        });
+        if let Some(subject) = assertion.subject {
+            claims["sub"] = subject.into();
+        }
+        if let Some(scope) = params.scope {
+            claims["scope"] = scope.into();
+        }
+        let assertion = jwt::encode(&claims, signer).await?;
+
+        let body = {
+            // Serializer can't go across await points. See https://github.com/servo/rust-url/pull/550
+            let mut body = Serializer::new(String::new());
+            body.append_pair("grant_type", "urn:ietf:params:oauth:grant-type:jwt-bearer");
+            body.append_pair("assertion", &assertion);
+            params.append_to(&mut body);
+            body.finish()
+        };
+
+        let json = self.post_token(http_client, body).await?;
+        let token = P::Token::from_response(&json)?;
+        Ok(token)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+    use url::form_urlencoded;
+
+    use crate::client::test_util::{MockHttp, Test};
+    use crate::jwt::{test_keys, PrivateKey};
+    use crate::token::Token;
+
+    #[tokio::test]
+    async fn request_jwt_bearer_token() {
+        let client = Client::new(Test::new(), String::from("foo"), String::from("bar"), None);
+        let key = PrivateKey::from_pem(test_keys::RSA_PKCS8).unwrap();
+        let http = MockHttp::new(&[r#"{"token_type":"Bearer","access_token":"aaaaaaaa"}"#]);
+
+        let token = client
+            .request_jwt_bearer_token(
+                &http,
+                &key,
+                &AssertionParams {
+                    issuer: "svc@example.com",
+                    subject: Some("alice"),
+                    audience: None,
+                    lifetime: Duration::from_secs(3600),
+                },
+                &GrantParams {
+                    scope: Some("baz"),
+                    resource: &["https://api.example.com/"],
+                    ..GrantParams::default()
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!("aaaaaaaa", token.access_token());
+
+        let body = http.requests().remove(0);
+        let params: Vec<(String, String)> =
+            form_urlencoded::parse(body.as_bytes()).into_owned().collect();
+        let param = |name| params.iter().find(|(k, _)| k == name).map(|(_, v)| &v[..]);
+        assert_eq!(Some("urn:ietf:params:oauth:grant-type:jwt-bearer"), param("grant_type"));
+        assert_eq!(Some("baz"), param("scope"));
+        assert_eq!(Some("https://api.example.com/"), param("resource"));
+
+        let assertion = param("assertion").unwrap();
+        let payload = assertion.split('.').nth(1).unwrap();
+        let claims: Value = serde_json::from_slice(
+            &base64::decode_config(payload, base64::URL_SAFE_NO_PAD).unwrap(),
+        ).unwrap();
+        assert_eq!("svc@example.com", claims["iss"]);
+        assert_eq!("alice", claims["sub"]);
+        assert_eq!("http://example.com/oauth2/token", claims["aud"]);
+        assert_eq!("baz", claims["scope"]);
+        assert_eq!(
+            3600,
+            claims["exp"].as_u64().unwrap() - claims["iat"].as_u64().unwrap()
+        );
+    }
+}
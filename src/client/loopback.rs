@@ -0,0 +1,343 @@
+//! Loopback redirect for native applications.
+//!
+//! Receives the authorization response on an ephemeral port of `127.0.0.1`, replacing the
+//! deprecated out-of-band redirect URIs.
+//!
+//! See [RFC 8252, section 7.3](https://tools.ietf.org/html/rfc8252#section-7.3).
+
+use std::io;
+use std::time::Duration;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::time::timeout;
+use url::Url;
+
+use crate::client::callback::{verify_state, ResponseParams};
+use crate::client::{
+    AuthParams, AuthorizationResponse, Client, ClientError, HttpClient, TokenParams,
+};
+use crate::pkce::Pkce;
+use crate::provider::Provider;
+use crate::util::random_token;
+
+/// Maximum size of the request head accepted by the listener.
+const MAX_REQUEST_LEN: usize = 8 * 1024;
+
+/// Time allowed for a connection to send its request head. Browsers may open connections
+/// speculatively and never use them; those would otherwise block the listener.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(2);
+
+const SUCCESS_PAGE: &str = "<!DOCTYPE html><html><head><title>Authorization complete</title></head>\
+    <body><p>Authorization complete. You can close this window.</p></body></html>";
+
+const FAILURE_PAGE: &str = "<!DOCTYPE html><html><head><title>Authorization failed</title></head>\
+    <body><p>Authorization failed. You can close this window.</p></body></html>";
+
+/// A listener for a single authorization response on `127.0.0.1`.
+///
+/// Requests for paths other than `/`, such as the browser's `/favicon.ico`, are answered with
+/// `404 Not Found` and otherwise ignored, as are connections which fail or don't send a `GET`
+/// request in time. Requests to `/` which are not an authorization response with the expected
+/// state, such as probes or stale tabs, are answered with `400 Bad Request` and ignored.
+#[derive(Debug)]
+pub struct LoopbackListener {
+    listener: TcpListener,
+    redirect_uri: String,
+    success_page: String,
+    failure_page: String,
+}
+
+impl LoopbackListener {
+    /// Binds an ephemeral port on `127.0.0.1`.
+    pub async fn bind() -> io::Result<Self> {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).await?;
+        let redirect_uri = format!("http://127.0.0.1:{}/", listener.local_addr()?.port());
+        Ok(LoopbackListener {
+            listener,
+            redirect_uri,
+            success_page: String::from(SUCCESS_PAGE),
+            failure_page: String::from(FAILURE_PAGE),
+        })
+    }
+
+    /// Returns the redirect URI to register with the client, `http://127.0.0.1:<port>/`.
+    pub fn redirect_uri(&self) -> &str { &self.redirect_uri }
+
+    /// Sets the HTML page shown in the browser after a successful authorization.
+    pub fn with_success_page(mut self, html: impl Into<String>) -> Self {
+        self.success_page = html.into();
+        self
+    }
+
+    /// Sets the HTML page shown in the browser after a failed authorization.
+    pub fn with_failure_page(mut self, html: impl Into<String>) -> Self {
+        self.failure_page = html.into();
+        self
+    }
+
+    /// Waits for the authorization response whose `state` equals `expected_state`.
+    ///
+    /// Any local process can connect to the listener, so the state is required. Error responses
+    /// are returned as `ClientError::OAuth2` errors once their state is verified.
+    ///
+    /// This waits indefinitely; wrap it in a timeout such as `tokio::time::timeout` if the user
+    /// may abandon the authorization.
+    pub async fn receive(
+        self,
+        expected_state: &str,
+    ) -> Result<AuthorizationResponse, ClientError> {
+        loop {
+            let (mut stream, _) = self.listener.accept().await?;
+            let target = match timeout(REQUEST_TIMEOUT, read_request_target(&mut stream)).await {
+                Ok(Ok(target)) => target,
+                Ok(Err(err)) => {
+                    log::debug!("ignoring loopback connection: {}", err);
+                    continue;
+                }
+                Err(_) => {
+                    log::debug!("ignoring loopback connection: no request received");
+                    continue;
+                }
+            };
+
+            let url = match Url::parse(&self.redirect_uri)?.join(&target) {
+                Ok(url) if url.path() == "/" => url,
+                _ => {
+                    if let Err(err) = respond(&mut stream, "404 Not Found", "").await {
+                        log::debug!("failed to respond to loopback request: {}", err);
+                    }
+                    continue;
+                }
+            };
+
+            let params = match ResponseParams::from_url(&url) {
+                Ok(params) if params.is_response() => params,
+                _ => {
+                    log::debug!("ignoring loopback request without an authorization response");
+                    if let Err(err) = respond(&mut stream, "400 Bad Request", "").await {
+                        log::debug!("failed to respond to loopback request: {}", err);
+                    }
+                    continue;
+                }
+            };
+            if verify_state(params.state.as_deref(), expected_state).is_err() {
+                log::debug!("ignoring loopback request with an unexpected state");
+                if let Err(err) = respond(&mut stream, "400 Bad Request", "").await {
+                    log::debug!("failed to respond to loopback request: {}", err);
+                }
+                continue;
+            }
+
+            let result = params.into_response();
+            let written = match result {
+                Ok(_) => respond(&mut stream, "200 OK", &self.success_page).await,
+                Err(_) => respond(&mut stream, "400 Bad Request", &self.failure_page).await,
+            };
+            // The response has been received; failing to show the page doesn't change it.
+            if let Err(err) = written {
+                log::debug!("failed to respond to loopback callback: {}", err);
+            }
+            return result;
+        }
+    }
+}
+
+/// Reads an HTTP request head and returns the request target of a `GET` request.
+async fn read_request_target(stream: &mut TcpStream) -> io::Result<String> {
+    let mut buf = Vec::new();
+    while !buf.windows(4).any(|w| w == b"\r\n\r\n") {
+        if buf.len() > MAX_REQUEST_LEN {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "request too long"));
+        }
+        let mut chunk = [0; 1024];
+        let n = stream.read(&mut chunk).await?;
+        if n == 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        buf.extend_from_slice(&chunk[..n]);
+    }
+
+    let head = String::from_utf8_lossy(&buf);
+    let mut request_line = head.lines().next().unwrap_or("").split(' ');
+    match (request_line.next(), request_line.next()) {
+        (Some("GET"), Some(target)) if target.starts_with('/') => Ok(target.to_owned()),
+        _ => Err(io::Error::new(io::ErrorKind::InvalidData, "not a GET request")),
+    }
+}
+
+async fn respond(stream: &mut TcpStream, status: &str, html: &str) -> io::Result<()> {
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\n\
+         Connection: close\r\n\r\n{}",
+        status,
+        html.len(),
+        html,
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+impl<P: Provider> Client<P> {
+    /// Runs the authorization code flow through a loopback redirect, for command-line tools.
+    ///
+    /// Binds a [`LoopbackListener`] and passes the authorization URI, redirecting to it instead of
+    /// the client's `redirect_uri` and with a random state and a PKCE challenge, to `open`, which
+    /// should open it in a browser or print it. The authorization code received is checked with
+    /// [`Client::verify_issuer`] and exchanged for a token. The client itself is not modified.
+    ///
+    /// See [RFC 8252](https://tools.ietf.org/html/rfc8252).
+    pub async fn authorize_loopback<F>(
+        &self,
+        http_client: &impl HttpClient,
+        scope: Option<&str>,
+        open: F,
+    ) -> Result<P::Token, ClientError>
+    where
+        F: FnOnce(&Url),
+    {
+        let listener = LoopbackListener::bind().await?;
+        self.authorize_with_listener(http_client, listener, scope, open).await
+    }
+
+    /// Like [`Client::authorize_loopback`], with a listener whose pages have been customized.
+    pub async fn authorize_with_listener<F>(
+        &self,
+        http_client: &impl HttpClient,
+        listener: LoopbackListener,
+        scope: Option<&str>,
+        open: F,
+    ) -> Result<P::Token, ClientError>
+    where
+        F: FnOnce(&Url),
+    {
+        let redirect_uri = listener.redirect_uri().to_owned();
+        let state = random_token(16);
+        let pkce = Pkce::s256();
+        open(&self.auth_uri_with(&AuthParams {
+            redirect_uri: Some(&redirect_uri),
+            scope,
+            state: Some(&state),
+            pkce: Some(&pkce),
+            ..AuthParams::default()
+        }));
+
+        let response = listener.receive(&state).await?;
+        self.verify_issuer(&response)?;
+        let params = TokenParams {
+            redirect_uri: Some(&redirect_uri),
+            pkce: Some(&pkce),
+            ..TokenParams::default()
+        };
+        self.request_token_with(http_client, &response.code, &params).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::client::test_util::{MockHttp, Test};
+    use crate::token::Token;
+
+    /// Sends a `GET` request as a browser would, returning the response.
+    async fn get(url: &str) -> String {
+        let url = Url::parse(url).unwrap();
+        let mut stream =
+            TcpStream::connect((url.host_str().unwrap(), url.port().unwrap())).await.unwrap();
+        let target = match url.query() {
+            Some(query) => format!("{}?{}", url.path(), query),
+            None => url.path().to_owned(),
+        };
+        stream
+            .write_all(format!("GET {} HTTP/1.1\r\nHost: 127.0.0.1\r\n\r\n", target).as_bytes())
+            .await
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        response
+    }
+
+    #[tokio::test]
+    async fn receive() {
+        let listener = LoopbackListener::bind().await.unwrap().with_success_page("yay");
+        let redirect_uri = listener.redirect_uri().to_owned();
+        assert!(redirect_uri.starts_with("http://127.0.0.1:"));
+
+        let browser = tokio::spawn(async move {
+            // A connection closed without a request, and one left open without a request, such as
+            // a browser's speculative ones.
+            let url = Url::parse(&redirect_uri).unwrap();
+            let port = url.port().unwrap();
+            drop(TcpStream::connect(("127.0.0.1", port)).await.unwrap());
+            let idle = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+            let not_found = get(&format!("{}favicon.ico", redirect_uri)).await;
+            let probe = get(&redirect_uri).await;
+            let forged = get(&format!("{}?code=evil&state=xyw", redirect_uri)).await;
+            let callback = get(&format!("{}?code=abc&state=xyz", redirect_uri)).await;
+            drop(idle);
+            (not_found, probe, forged, callback)
+        });
+        let response = listener.receive("xyz").await.unwrap();
+        assert_eq!("abc", response.code);
+
+        let (not_found, probe, forged, callback) = browser.await.unwrap();
+        assert!(not_found.starts_with("HTTP/1.1 404 Not Found\r\n"));
+        assert!(probe.starts_with("HTTP/1.1 400 Bad Request\r\n"));
+        assert!(forged.starts_with("HTTP/1.1 400 Bad Request\r\n"));
+        assert!(callback.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(callback.ends_with("\r\n\r\nyay"));
+    }
+
+    #[tokio::test]
+    async fn receive_error() {
+        let listener = LoopbackListener::bind().await.unwrap().with_failure_page("nay");
+        let redirect_uri = listener.redirect_uri().to_owned();
+
+        let browser = tokio::spawn(async move {
+            get(&format!("{}?error=access_denied&state=xyz", redirect_uri)).await
+        });
+        assert!(matches!(listener.receive("xyz").await, Err(ClientError::OAuth2(_))));
+        let response = browser.await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 400 Bad Request\r\n"));
+        assert!(response.ends_with("nay"));
+    }
+
+    #[tokio::test]
+    async fn authorize_loopback() {
+        let client = Client::new(
+            Test::new(),
+            String::from("foo"),
+            String::from("bar"),
+            Some(String::from("https://client.example.com/cb")),
+        );
+        let http = MockHttp::new(&[r#"{"token_type":"Bearer","access_token":"aaaaaaaa"}"#]);
+
+        let (tx, rx) = tokio::sync::oneshot::channel::<Url>();
+        let browser = tokio::spawn(async move {
+            // Play the authorization server: redirect back with a code and the state.
+            let auth_uri = rx.await.unwrap();
+            let param = |name| auth_uri.query_pairs().find(|(k, _)| k == name).unwrap().1;
+            let redirect_uri = param("redirect_uri").into_owned();
+            let response =
+                get(&format!("{}?code=abc&state={}", redirect_uri, param("state"))).await;
+            (redirect_uri, response)
+        });
+
+        let token = client
+            .authorize_loopback(&http, Some("baz"), |uri| tx.send(uri.clone()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!("aaaaaaaa", token.access_token());
+        let (redirect_uri, response) = browser.await.unwrap();
+        assert!(redirect_uri.starts_with("http://127.0.0.1:"));
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert_eq!(Some("https://client.example.com/cb"), client.redirect_uri.as_deref());
+
+        let body = &http.requests()[0];
+        assert!(body.starts_with("grant_type=authorization_code&code=abc&redirect_uri="));
+        assert!(body.contains(&url::form_urlencoded::byte_serialize(redirect_uri.as_bytes())
+            .collect::<String>()));
+        assert!(body.contains("&code_verifier="));
+    }
+}
@@ -3,18 +3,61 @@
 mod error;
 pub use error::ClientError;
 
+pub mod auth;
+pub use auth::ClientAuth;
+
 pub mod http_client;
 pub use http_client::HttpClient;
 
 pub mod response;
 
+pub mod params;
+pub use params::{AuthParams, GrantParams, TokenParams};
+
+pub mod device;
+pub use device::DeviceAuthorization;
+
+pub mod jwt_bearer;
+pub use jwt_bearer::AssertionParams;
+
+pub mod token_exchange;
+pub use token_exchange::{ExchangedToken, TokenExchangeParams};
+
+pub mod revocation;
+
+pub mod introspection;
+pub use introspection::Introspection;
+
+pub mod userinfo;
+pub use userinfo::UserInfoParams;
+
+pub mod callback;
+pub use callback::AuthorizationResponse;
+
+pub mod par;
+pub use par::PushedAuthorization;
+
+pub mod jar;
+
+pub mod registration;
+pub use registration::{ClientMetadata, ClientRegistration};
+
+#[cfg(feature = "loopback")]
+pub mod loopback;
+#[cfg(feature = "loopback")]
+pub use loopback::LoopbackListener;
+
+#[cfg(test)]
+pub(crate) mod test_util;
+
 use serde_json::{self, Value};
 use url::form_urlencoded::Serializer;
 use url::Url;
 
-use crate::client::response::FromResponse;
+use crate::client::response::{FromResponse, ParseError};
 use crate::error::OAuth2Error;
 use crate::provider::Provider;
+use crate::rar;
 use crate::token::{Lifetime, Refresh, Token};
 
 /// OAuth 2.0 client.
@@ -31,6 +74,10 @@ pub struct Client<P> {
 
     /// Redirect URI.
     pub redirect_uri: Option<String>,
+
+    /// Client authentication method. Defaults to the provider's
+    /// [`Provider::client_auth`].
+    pub auth: ClientAuth,
 }
 
 impl<P: Provider> Client<P> {
@@ -55,14 +102,31 @@ impl<P: Provider> Client<P> {
         client_secret: String,
         redirect_uri: Option<String>,
     ) -> Self {
+        let auth = provider.client_auth();
         Client {
             provider,
             client_id,
             client_secret,
             redirect_uri,
+            auth,
         }
     }
 
+    /// Creates a public client, which has no secret and does not authenticate.
+    ///
+    /// Public clients, such as native and browser applications, should use PKCE.
+    ///
+    /// See [RFC 6749, section 2.1](http://tools.ietf.org/html/rfc6749#section-2.1).
+    pub fn public(provider: P, client_id: String, redirect_uri: Option<String>) -> Self {
+        Client::new(provider, client_id, String::new(), redirect_uri).with_auth(ClientAuth::None)
+    }
+
+    /// Sets the client authentication method.
+    pub fn with_auth(mut self, auth: ClientAuth) -> Self {
+        self.auth = auth;
+        self
+    }
+
     /// Returns an authorization endpoint URI to direct the user to.
     ///
     /// See [RFC 6749, section 3.1](http://tools.ietf.org/html/rfc6749#section-3.1).
@@ -85,28 +149,72 @@ impl<P: Provider> Client<P> {
     ///     None,
     /// );
     /// ```
-    pub fn auth_uri(&self, scope: Option<&str>, state: Option<&str>) -> Url
-    {
-        let mut uri = self.provider.auth_uri().clone();
+    pub fn auth_uri(&self, scope: Option<&str>, state: Option<&str>) -> Url {
+        self.auth_uri_with(&AuthParams { scope, state, ..AuthParams::default() })
+    }
 
-        {
-            let mut query = uri.query_pairs_mut();
+    /// Returns an authorization endpoint URI with additional parameters, such as a PKCE
+    /// challenge.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use inth_oauth2_async::client::AuthParams;
+    /// use inth_oauth2_async::pkce::Pkce;
+    /// use inth_oauth2_async::Client;
+    /// use inth_oauth2_async::provider::google::Installed;
+    ///
+    /// let client = Client::new(
+    ///     Installed,
+    ///     String::from("CLIENT_ID"),
+    ///     String::from("CLIENT_SECRET"),
+    ///     Some(String::from("http://127.0.0.1:8080/")),
+    /// );
+    ///
+    /// let pkce = Pkce::s256();
+    /// let auth_uri = client.auth_uri_with(&AuthParams {
+    ///     scope: Some("https://www.googleapis.com/auth/userinfo.email"),
+    ///     pkce: Some(&pkce),
+    ///     ..AuthParams::default()
+    /// });
+    /// ```
+    pub fn auth_uri_with(&self, params: &AuthParams) -> Url {
+        let mut uri = self.provider.auth_uri().clone();
+        uri.query_pairs_mut().extend_pairs(self.auth_request_params(params));
+        uri
+    }
 
-            query.append_pair("response_type", "code");
-            query.append_pair("client_id", &self.client_id);
+    /// Returns the parameters of an authorization request, in the order they are sent.
+    fn auth_request_params(&self, params: &AuthParams) -> Vec<(&'static str, String)> {
+        let mut pairs = vec![
+            ("response_type", String::from("code")),
+            ("client_id", self.client_id.clone()),
+        ];
 
-            if let Some(ref redirect_uri) = self.redirect_uri {
-                query.append_pair("redirect_uri", redirect_uri);
-            }
-            if let Some(scope) = scope {
-                query.append_pair("scope", scope);
-            }
-            if let Some(state) = state {
-                query.append_pair("state", state);
-            }
+        if let Some(redirect_uri) = params.redirect_uri.or(self.redirect_uri.as_deref()) {
+            pairs.push(("redirect_uri", redirect_uri.to_owned()));
+        }
+        if let Some(scope) = params.scope {
+            pairs.push(("scope", scope.to_owned()));
+        }
+        if let Some(state) = params.state {
+            pairs.push(("state", state.to_owned()));
+        }
+        if let Some(pkce) = params.pkce {
+            pairs.push(("code_challenge", pkce.challenge()));
+            pairs.push(("code_challenge_method", pkce.method().as_str().to_owned()));
+        }
+        if let Some(nonce) = params.nonce {
+            pairs.push(("nonce", nonce.as_str().to_owned()));
+        }
+        if let Some(details) = params.authorization_details {
+            pairs.push(("authorization_details", rar::to_param(details)));
+        }
+        for resource in params.resource {
+            pairs.push(("resource", (*resource).to_owned()));
         }
 
-        uri
+        pairs
     }
 
     async fn post_token(
@@ -114,24 +222,27 @@ impl<P: Provider> Client<P> {
         http_client: &impl HttpClient,
         body: String,
     ) -> Result<Value, ClientError> {
-        let body = {
-            // Serializer can't go across await points. See https://github.com/servo/rust-url/pull/550
-            let mut body = Serializer::new(body);
-            if self.provider.credentials_in_body() {
-                body.append_pair("client_id", &self.client_id);
-                body.append_pair("client_secret", &self.client_secret);
-            }
-            body.finish()
-        };
+        self.post_endpoint(http_client, self.provider.token_uri(), body).await
+    }
 
-        let json = http_client
-            .post(
-                self.provider.token_uri().as_str(),
-                &self.client_id,
-                &self.client_secret,
-                body,
-            )
-            .await?;
+    /// Posts an authenticated request to one of the provider's endpoints, detecting OAuth 2.0
+    /// error responses. Unsuccessful responses without one are `ClientError::HttpStatus` errors.
+    async fn post_endpoint(
+        &self,
+        http_client: &impl HttpClient,
+        uri: &Url,
+        body: String,
+    ) -> Result<Value, ClientError> {
+        let request = self.authenticated_request(uri, body).await?;
+        let response = http_client.send(request).await?;
+        if !response.is_success() {
+            let error = response.json().ok().and_then(|json| OAuth2Error::from_response(&json).ok());
+            return Err(match error {
+                Some(error) => error.into(),
+                None => ClientError::HttpStatus(response.status),
+            });
+        }
+        let json = response.json()?;
 
         let error = OAuth2Error::from_response(&json);
 
@@ -142,6 +253,59 @@ impl<P: Provider> Client<P> {
         }
     }
 
+    /// Requests a token using an extension grant type.
+    ///
+    /// The request is authenticated like every other token request, OAuth 2.0 error responses
+    /// are returned as `ClientError::OAuth2`, and successful responses are parsed as `T`, usually
+    /// `P::Token`.
+    ///
+    /// See [RFC 6749, section 4.5](http://tools.ietf.org/html/rfc6749#section-4.5).
+    ///
+    /// # Examples
+    ///
+    /// Using the SAML 2.0 bearer assertion grant of
+    /// [RFC 7522](https://tools.ietf.org/html/rfc7522):
+    ///
+    /// ```no_run
+    /// # #[cfg(feature="reqwest-client")] {
+    /// use inth_oauth2_async::Client;
+    /// use inth_oauth2_async::provider::google::Web;
+    /// use inth_oauth2_async::token::{Bearer, Expiring};
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// # let client = Client::new(Web, String::new(), String::new(), None);
+    /// # let http = reqwest::Client::new();
+    /// # let assertion = "";
+    ///
+    /// let token: Bearer<Expiring> = client
+    ///     .request_grant(
+    ///         &http,
+    ///         "urn:ietf:params:oauth:grant-type:saml2-bearer",
+    ///         &[("assertion", assertion)],
+    ///     )
+    ///     .await
+    ///     .unwrap();
+    /// # } }
+    /// ```
+    pub async fn request_grant<T: FromResponse>(
+        &self,
+        http_client: &impl HttpClient,
+        grant_type: &str,
+        params: &[(&str, &str)],
+    ) -> Result<T, ClientError> {
+        let body = {
+            // Serializer can't go across await points. See https://github.com/servo/rust-url/pull/550
+            let mut body = Serializer::new(String::new());
+            body.append_pair("grant_type", grant_type);
+            body.extend_pairs(params);
+            body.finish()
+        };
+
+        let json = self.post_token(http_client, body).await?;
+        let response = T::from_response(&json)?;
+        Ok(response)
+    }
+
     /// Requests an access token using an authorization code.
     ///
     /// See [RFC 6749, section 4.1.3](http://tools.ietf.org/html/rfc6749#section-4.1.3).
@@ -149,6 +313,19 @@ impl<P: Provider> Client<P> {
         &self,
         http_client: &impl HttpClient,
         code: &str,
+    ) -> Result<P::Token, ClientError> {
+        self.request_token_with(http_client, code, &TokenParams::default()).await
+    }
+
+    /// Requests an access token using an authorization code and additional parameters, such as
+    /// the PKCE verifier used in the authorization request.
+    ///
+    /// See [RFC 7636, section 4.5](https://tools.ietf.org/html/rfc7636#section-4.5).
+    pub async fn request_token_with(
+        &self,
+        http_client: &impl HttpClient,
+        code: &str,
+        params: &TokenParams<'_>,
     ) -> Result<P::Token, ClientError> {
         let body = {
             // Serializer can't go across await points. See https://github.com/servo/rust-url/pull/550
@@ -156,84 +333,216 @@ impl<P: Provider> Client<P> {
             body.append_pair("grant_type", "authorization_code");
             body.append_pair("code", code);
 
-            if let Some(ref redirect_uri) = self.redirect_uri {
+            if let Some(redirect_uri) = params.redirect_uri.or(self.redirect_uri.as_deref()) {
                 body.append_pair("redirect_uri", redirect_uri);
             }
+            if let Some(pkce) = params.pkce {
+                body.append_pair("code_verifier", pkce.verifier());
+            }
+            if let Some(details) = params.authorization_details {
+                body.append_pair("authorization_details", &rar::to_param(details));
+            }
+            for resource in params.resource {
+                body.append_pair("resource", resource);
+            }
 
             body.finish()
         };
 
         let json = self.post_token(http_client, body).await?;
         let token = P::Token::from_response(&json)?;
+        if let Some(nonce) = params.nonce {
+            let id_token = token.id_token()
+                .ok_or(ParseError::ExpectedFieldType("id_token", "string"))?;
+            nonce.verify_id_token(id_token)?;
+        }
         Ok(token)
     }
-}
 
-impl<P> Client<P> where P: Provider, P::Token: Token<Refresh> {
-    /// Refreshes an access token.
+    /// Requests an access token using the client's own credentials.
     ///
-    /// See [RFC 6749, section 6](http://tools.ietf.org/html/rfc6749#section-6).
-    pub async fn refresh_token(
+    /// See [RFC 6749, section 4.4](http://tools.ietf.org/html/rfc6749#section-4.4).
+    pub async fn request_client_credentials_token(
         &self,
         http_client: &impl HttpClient,
-        token: P::Token,
         scope: Option<&str>,
+    ) -> Result<P::Token, ClientError> {
+        let params = GrantParams { scope, ..GrantParams::default() };
+        self.request_client_credentials_token_with(http_client, &params).await
+    }
+
+    /// Requests an access token using the client's own credentials and additional parameters,
+    /// such as resource indicators.
+    ///
+    /// See [RFC 8707, section 2.2](https://tools.ietf.org/html/rfc8707#section-2.2).
+    pub async fn request_client_credentials_token_with(
+        &self,
+        http_client: &impl HttpClient,
+        params: &GrantParams<'_>,
+    ) -> Result<P::Token, ClientError> {
+        let body = {
+            // Serializer can't go across await points. See https://github.com/servo/rust-url/pull/550
+            let mut body = Serializer::new(String::new());
+            body.append_pair("grant_type", "client_credentials");
+            params.append_to(&mut body);
+            body.finish()
+        };
+
+        let json = self.post_token(http_client, body).await?;
+        let token = P::Token::from_response(&json)?;
+        Ok(token)
+    }
+
+    /// Requests an access token using the resource owner's username and password.
+    ///
+    /// This grant is discouraged by the OAuth 2.0 Security Best Current Practice and removed in
+    /// OAuth 2.1, so it requires the `legacy-password-grant` feature.
+    ///
+    /// See [RFC 6749, section 4.3](http://tools.ietf.org/html/rfc6749#section-4.3).
+    #[cfg(feature = "legacy-password-grant")]
+    pub async fn request_password_token(
+        &self,
+        http_client: &impl HttpClient,
+        username: &str,
+        password: &str,
+        scope: Option<&str>,
+    ) -> Result<P::Token, ClientError> {
+        let params = GrantParams { scope, ..GrantParams::default() };
+        self.request_password_token_with(http_client, username, password, &params).await
+    }
+
+    /// Requests an access token using the resource owner's username and password, and additional
+    /// parameters, such as resource indicators.
+    ///
+    /// Requires the `legacy-password-grant` feature.
+    #[cfg(feature = "legacy-password-grant")]
+    pub async fn request_password_token_with(
+        &self,
+        http_client: &impl HttpClient,
+        username: &str,
+        password: &str,
+        params: &GrantParams<'_>,
     ) -> Result<P::Token, ClientError> {
         let body = {
             // Serializer can't go across await points. See https://github.com/servo/rust-url/pull/550
             let mut body = Serializer::new(String::new());
-            body.append_pair("grant_type", "refresh_token");
-            body.append_pair("refresh_token", token.lifetime().refresh_token());
+            body.append_pair("grant_type", "password");
+            body.append_pair("username", username);
+            body.append_pair("password", password);
+            params.append_to(&mut body);
+            body.finish()
+        };
 
-            if let Some(scope) = scope {
-                body.append_pair("scope", scope);
-            }
+        let json = self.post_token(http_client, body).await?;
+        let token = P::Token::from_response(&json)?;
+        Ok(token)
+    }
 
+    async fn post_refresh_token(
+        &self,
+        http_client: &impl HttpClient,
+        token: &P::Token,
+        refresh_token: &str,
+        params: &GrantParams<'_>,
+    ) -> Result<P::Token, ClientError> {
+        let body = {
+            // Serializer can't go across await points. See https://github.com/servo/rust-url/pull/550
+            let mut body = Serializer::new(String::new());
+            body.append_pair("grant_type", "refresh_token");
+            body.append_pair("refresh_token", refresh_token);
+            params.append_to(&mut body);
             body.finish()
         };
 
         let json = self.post_token(http_client, body).await?;
-        let token = P::Token::from_response_inherit(&json, &token)?;
+        let token = P::Token::from_response_inherit(&json, token)?;
         Ok(token)
     }
 
     /// Ensures an access token is valid by refreshing it if necessary.
+    ///
+    /// Expired tokens without a refresh token fail with `ClientError::TokenExpired`; they are not
+    /// replaced with client credentials tokens, which would silently swap a user's token for one
+    /// representing the client. Tokens obtained with
+    /// [`Client::request_client_credentials_token_with`] are renewed with
+    /// [`Client::ensure_client_credentials_token`] instead.
     pub async fn ensure_token(
         &self,
         http_client: &impl HttpClient,
         token: P::Token,
     ) -> Result<P::Token, ClientError> {
-        if token.lifetime().expired() {
-            self.refresh_token(http_client, token, None).await
-        } else {
-            Ok(token)
+        if !token.lifetime().expired() {
+            return Ok(token);
+        }
+        let refresh_token = token.lifetime().refresh_token()
+            .ok_or(ClientError::TokenExpired)?;
+        let params = GrantParams::default();
+        self.post_refresh_token(http_client, &token, refresh_token, &params).await
+    }
+
+    /// Ensures an access token obtained with the client credentials grant is valid, requesting a
+    /// new one with `params` if it has expired.
+    ///
+    /// `params` must be those of the original request: the token's own scope is not reused, as
+    /// servers may omit it from responses when it is unchanged, and resource indicators are not
+    /// returned at all.
+    ///
+    /// Only use this for tokens issued to the client itself: the new token never represents a
+    /// user, whatever the expired one did.
+    pub async fn ensure_client_credentials_token(
+        &self,
+        http_client: &impl HttpClient,
+        token: P::Token,
+        params: &GrantParams<'_>,
+    ) -> Result<P::Token, ClientError> {
+        if !token.lifetime().expired() {
+            return Ok(token);
         }
+        self.request_client_credentials_token_with(http_client, params).await
+    }
+}
+
+impl<P> Client<P> where P: Provider, P::Token: Token<Refresh> {
+    /// Refreshes an access token.
+    ///
+    /// See [RFC 6749, section 6](http://tools.ietf.org/html/rfc6749#section-6).
+    pub async fn refresh_token(
+        &self,
+        http_client: &impl HttpClient,
+        token: P::Token,
+        scope: Option<&str>,
+    ) -> Result<P::Token, ClientError> {
+        let params = GrantParams { scope, ..GrantParams::default() };
+        self.refresh_token_with(http_client, &token, &params).await
+    }
+
+    /// Refreshes an access token with additional parameters, such as a single resource
+    /// indicator to obtain a token restricted to one resource server of a multi-resource grant.
+    ///
+    /// The token is borrowed so that it can be refreshed again for other resources.
+    ///
+    /// See [RFC 8707, section 2.2](https://tools.ietf.org/html/rfc8707#section-2.2).
+    pub async fn refresh_token_with(
+        &self,
+        http_client: &impl HttpClient,
+        token: &P::Token,
+        params: &GrantParams<'_>,
+    ) -> Result<P::Token, ClientError> {
+        self.post_refresh_token(http_client, token, token.lifetime().refresh_token(), params)
+            .await
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::token::{Bearer, Static};
-
-    struct Test {
-        auth_uri: Url,
-        token_uri: Url
-    }
-    impl Provider for Test {
-        type Lifetime = Static;
-        type Token = Bearer<Static>;
-        fn auth_uri(&self) -> &Url { &self.auth_uri }
-        fn token_uri(&self) -> &Url { &self.token_uri }
-    }
-    impl Test {
-        fn new() -> Self {
-            Test {
-                auth_uri: Url::parse("http://example.com/oauth2/auth").unwrap(),
-                token_uri: Url::parse("http://example.com/oauth2/token").unwrap()
-            }
-        }
-    }
+    use crate::client::test_util::{MockHttp, Test, TestExpiring, TestRefresh};
+    use crate::error::OAuth2ErrorCode;
+    use crate::jwt::JwtError;
+    use crate::oidc::Nonce;
+    use crate::pkce::Pkce;
+    use crate::rar::AuthorizationDetail;
+    use crate::token::{Bearer, Expiring, Static};
 
     #[test]
     fn auth_uri() {
@@ -275,4 +584,309 @@ mod tests {
             client.auth_uri(None, Some("baz")).as_str()
         );
     }
+
+    #[test]
+    fn auth_uri_with_pkce() {
+        let client = Client::new(Test::new(), String::from("foo"), String::from("bar"), None);
+        let pkce = Pkce::s256();
+        let uri = client.auth_uri_with(&AuthParams { pkce: Some(&pkce), ..AuthParams::default() });
+        assert_eq!(
+            format!(
+                "http://example.com/oauth2/auth?response_type=code&client_id=foo&code_challenge={}&code_challenge_method=S256",
+                pkce.challenge(),
+            ),
+            uri.as_str()
+        );
+    }
+
+    #[test]
+    fn auth_uri_with_nonce() {
+        let client = Client::new(Test::new(), String::from("foo"), String::from("bar"), None);
+        let nonce = Nonce::from(String::from("n-0S6_WzA2Mj"));
+        let uri = client.auth_uri_with(&AuthParams {
+            scope: Some("openid"),
+            nonce: Some(&nonce),
+            ..AuthParams::default()
+        });
+        assert_eq!(
+            "http://example.com/oauth2/auth?response_type=code&client_id=foo&scope=openid&nonce=n-0S6_WzA2Mj",
+            uri.as_str()
+        );
+    }
+
+    #[test]
+    fn auth_uri_with_authorization_details() {
+        let client = Client::new(Test::new(), String::from("foo"), String::from("bar"), None);
+        let details = [AuthorizationDetail::new("account_information").with_actions(&["read"])];
+        let uri = client.auth_uri_with(&AuthParams {
+            authorization_details: Some(&details),
+            ..AuthParams::default()
+        });
+        assert_eq!(
+            "http://example.com/oauth2/auth?response_type=code&client_id=foo&authorization_details=%5B%7B%22type%22%3A%22account_information%22%2C%22actions%22%3A%5B%22read%22%5D%7D%5D",
+            uri.as_str()
+        );
+    }
+
+    #[tokio::test]
+    async fn request_token_with_authorization_details() {
+        let client = Client::new(Test::new(), String::from("foo"), String::from("bar"), None);
+        let http = MockHttp::new(&[r#"
+            {
+                "token_type":"Bearer",
+                "access_token":"aaaaaaaa",
+                "authorization_details":[{"type":"account_information","actions":["read"]}]
+            }
+        "#]);
+        let details = [AuthorizationDetail::new("account_information").with_actions(&["read"])];
+        let token = client
+            .request_token_with(&http, "abc", &TokenParams {
+                authorization_details: Some(&details),
+                ..TokenParams::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(Some(&details[..]), token.authorization_details());
+        assert_eq!(
+            vec![
+                "grant_type=authorization_code&code=abc&authorization_details=%5B%7B%22type%22%3A%22account_information%22%2C%22actions%22%3A%5B%22read%22%5D%7D%5D",
+            ],
+            http.requests()
+        );
+    }
+
+    #[tokio::test]
+    async fn request_token_with_nonce() {
+        let client = Client::new(Test::new(), String::from("foo"), String::from("bar"), None);
+        let nonce = Nonce::new();
+        let params = TokenParams { nonce: Some(&nonce), ..TokenParams::default() };
+        let response = |claims: Value| {
+            let id_token = crate::jwt::encode_hs256(&claims, b"secret");
+            MockHttp::new(&[&format!(
+                r#"{{"token_type":"Bearer","access_token":"aaaaaaaa","id_token":"{}"}}"#,
+                id_token
+            )])
+        };
+
+        let http = response(serde_json::json!({"sub": "alice", "nonce": nonce.as_str()}));
+        let token = client.request_token_with(&http, "abc", &params).await.unwrap();
+        assert!(token.id_token().is_some());
+
+        let http = response(serde_json::json!({"sub": "alice", "nonce": "replayed"}));
+        assert!(matches!(
+            client.request_token_with(&http, "abc", &params).await,
+            Err(ClientError::Jwt(JwtError::InvalidClaim("nonce")))
+        ));
+
+        let http = MockHttp::new(&[r#"{"token_type":"Bearer","access_token":"aaaaaaaa"}"#]);
+        assert!(matches!(
+            client.request_token_with(&http, "abc", &params).await,
+            Err(ClientError::Parse(ParseError::ExpectedFieldType("id_token", _)))
+        ));
+    }
+
+    #[tokio::test]
+    async fn client_credentials_token() {
+        let client = Client::new(Test::new(), String::from("foo"), String::from("bar"), None);
+        let http = MockHttp::new(&[r#"{"token_type":"Bearer","access_token":"aaaaaaaa"}"#]);
+        let token = client.request_client_credentials_token(&http, Some("baz")).await.unwrap();
+        assert_eq!("aaaaaaaa", token.access_token());
+        assert_eq!(vec!["grant_type=client_credentials&scope=baz"], http.requests());
+    }
+
+    #[test]
+    fn auth_uri_with_resource() {
+        let client = Client::new(Test::new(), String::from("foo"), String::from("bar"), None);
+        let uri = client.auth_uri_with(&AuthParams {
+            resource: &["https://a.example.com/", "https://b.example.com/"],
+            ..AuthParams::default()
+        });
+        assert_eq!(
+            "http://example.com/oauth2/auth?response_type=code&client_id=foo&resource=https%3A%2F%2Fa.example.com%2F&resource=https%3A%2F%2Fb.example.com%2F",
+            uri.as_str()
+        );
+    }
+
+    #[tokio::test]
+    async fn client_credentials_token_with_resource() {
+        let client = Client::new(Test::new(), String::from("foo"), String::from("bar"), None);
+        let http = MockHttp::new(&[r#"{"token_type":"Bearer","access_token":"aaaaaaaa"}"#]);
+        client
+            .request_client_credentials_token_with(&http, &GrantParams {
+                scope: Some("baz"),
+                resource: &["https://a.example.com/"],
+                ..GrantParams::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(
+            vec!["grant_type=client_credentials&scope=baz&resource=https%3A%2F%2Fa.example.com%2F"],
+            http.requests()
+        );
+    }
+
+    #[tokio::test]
+    async fn refresh_token_with_resource() {
+        let client = Client::new(
+            TestRefresh(Test::new()),
+            String::from("foo"),
+            String::from("bar"),
+            None,
+        );
+        let grant = r#"{"token_type":"Bearer","access_token":"aaaaaaaa","expires_in":3600,"refresh_token":"rrrr"}"#;
+        let token = Bearer::<Refresh>::from_response(&grant.parse().unwrap()).unwrap();
+
+        let http = MockHttp::new(&[
+            r#"{"token_type":"Bearer","access_token":"bbbbbbbb","expires_in":3600}"#,
+        ]);
+        for resource in &["https://a.example.com/", "https://b.example.com/"] {
+            let downscoped = client
+                .refresh_token_with(&http, &token, &GrantParams {
+                    resource: &[resource],
+                    ..GrantParams::default()
+                })
+                .await
+                .unwrap();
+            assert_eq!("bbbbbbbb", downscoped.access_token());
+            assert_eq!("rrrr", downscoped.lifetime().refresh_token());
+        }
+        assert_eq!(
+            vec![
+                "grant_type=refresh_token&refresh_token=rrrr&resource=https%3A%2F%2Fa.example.com%2F",
+                "grant_type=refresh_token&refresh_token=rrrr&resource=https%3A%2F%2Fb.example.com%2F",
+            ],
+            http.requests()
+        );
+    }
+
+    #[tokio::test]
+    async fn extension_grant() {
+        let client = Client::new(Test::new(), String::from("foo"), String::from("bar"), None);
+        let http = MockHttp::new(&[r#"{"token_type":"Bearer","access_token":"aaaaaaaa"}"#]);
+        let token: Bearer<Static> = client
+            .request_grant(&http, "urn:example:grant", &[("assertion", "a b")])
+            .await
+            .unwrap();
+        assert_eq!("aaaaaaaa", token.access_token());
+        assert_eq!(vec!["grant_type=urn%3Aexample%3Agrant&assertion=a+b"], http.requests());
+    }
+
+    #[tokio::test]
+    async fn extension_grant_error() {
+        let client = Client::new(Test::new(), String::from("foo"), String::from("bar"), None);
+        let http = MockHttp::new(&[r#"{"error":"unsupported_grant_type"}"#]);
+        let result = client
+            .request_grant::<Bearer<Static>>(&http, "urn:example:grant", &[])
+            .await;
+        assert!(matches!(
+            result,
+            Err(ClientError::OAuth2(OAuth2Error { code: OAuth2ErrorCode::UnsupportedGrantType, .. }))
+        ));
+    }
+
+    #[cfg(feature = "legacy-password-grant")]
+    #[tokio::test]
+    async fn password_token() {
+        let client = Client::new(Test::new(), String::from("foo"), String::from("bar"), None);
+        let http = MockHttp::new(&[r#"{"token_type":"Bearer","access_token":"aaaaaaaa"}"#]);
+        let token = client.request_password_token(&http, "alice", "p&ss", None).await.unwrap();
+        assert_eq!("aaaaaaaa", token.access_token());
+        assert_eq!(vec!["grant_type=password&username=alice&password=p%26ss"], http.requests());
+    }
+
+    #[cfg(feature = "legacy-password-grant")]
+    #[tokio::test]
+    async fn password_token_with_resource() {
+        let client = Client::new(Test::new(), String::from("foo"), String::from("bar"), None);
+        let http = MockHttp::new(&[r#"{"token_type":"Bearer","access_token":"aaaaaaaa"}"#]);
+        client
+            .request_password_token_with(&http, "alice", "pass", &GrantParams {
+                scope: Some("baz"),
+                resource: &["https://api.example.com/"],
+                ..GrantParams::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(
+            vec![
+                "grant_type=password&username=alice&password=pass&scope=baz\
+                 &resource=https%3A%2F%2Fapi.example.com%2F"
+            ],
+            http.requests()
+        );
+    }
+
+    #[tokio::test]
+    async fn ensure_client_credentials_token() {
+        let client = Client::new(
+            TestExpiring(Test::new()),
+            String::from("foo"),
+            String::from("bar"),
+            None,
+        );
+        // The scope is omitted from the response, as it is unchanged.
+        let expired = r#"{"token_type":"Bearer","access_token":"aaaaaaaa","expires_in":0}"#;
+        let token = Bearer::<Expiring>::from_response(&expired.parse().unwrap()).unwrap();
+        let params = GrantParams {
+            scope: Some("baz"),
+            resource: &["https://api.example.com/"],
+            ..GrantParams::default()
+        };
+
+        let http = MockHttp::new(&[
+            r#"{"token_type":"Bearer","access_token":"bbbbbbbb","expires_in":3600}"#,
+        ]);
+        let token = client.ensure_client_credentials_token(&http, token, &params).await.unwrap();
+        assert_eq!("bbbbbbbb", token.access_token());
+        assert_eq!(
+            vec![
+                "grant_type=client_credentials&scope=baz\
+                 &resource=https%3A%2F%2Fapi.example.com%2F"
+            ],
+            http.requests()
+        );
+
+        let token = client.ensure_client_credentials_token(&http, token, &params).await.unwrap();
+        assert_eq!("bbbbbbbb", token.access_token());
+        assert_eq!(1, http.requests().len());
+    }
+
+    #[tokio::test]
+    async fn ensure_token_refreshes() {
+        let client = Client::new(
+            TestRefresh(Test::new()),
+            String::from("foo"),
+            String::from("bar"),
+            None,
+        );
+        let expired = r#"{"token_type":"Bearer","access_token":"aaaaaaaa","expires_in":0,"refresh_token":"rrrr"}"#;
+        let token = Bearer::<Refresh>::from_response(&expired.parse().unwrap()).unwrap();
+
+        let http = MockHttp::new(&[
+            r#"{"token_type":"Bearer","access_token":"bbbbbbbb","expires_in":3600}"#,
+        ]);
+        let token = client.ensure_token(&http, token).await.unwrap();
+        assert_eq!("bbbbbbbb", token.access_token());
+        assert_eq!("rrrr", token.lifetime().refresh_token());
+        assert_eq!(vec!["grant_type=refresh_token&refresh_token=rrrr"], http.requests());
+    }
+
+    #[tokio::test]
+    async fn ensure_token_expired_without_refresh_token() {
+        let client = Client::new(
+            TestExpiring(Test::new()),
+            String::from("foo"),
+            String::from("bar"),
+            None,
+        );
+        let expired = r#"{"token_type":"Bearer","access_token":"aaaaaaaa","expires_in":0}"#;
+        let token = Bearer::<Expiring>::from_response(&expired.parse().unwrap()).unwrap();
+
+        let http = MockHttp::new(&["{}"]);
+        assert!(matches!(
+            client.ensure_token(&http, token).await,
+            Err(ClientError::TokenExpired)
+        ));
+        assert!(http.requests().is_empty());
+    }
 }
@@ -0,0 +1,210 @@
+//! Pushed authorization requests.
+//!
+//! See [RFC 9126](https://tools.ietf.org/html/rfc9126).
+
+use std::time::{Duration, SystemTime};
+
+use serde_json::Value;
+use url::form_urlencoded::Serializer;
+use url::Url;
+
+use crate::client::response::{FromResponse, ParseError};
+use crate::client::{AuthParams, Client, ClientError, HttpClient};
+use crate::provider::Provider;
+
+/// Pushed authorization response: a reference to the pushed request.
+///
+/// See [RFC 9126, section 2.2](https://tools.ietf.org/html/rfc9126#section-2.2).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PushedAuthorization {
+    request_uri: String,
+    expires: SystemTime,
+}
+
+impl PushedAuthorization {
+    /// Returns the request URI, which references the pushed parameters.
+    pub fn request_uri(&self) -> &str { &self.request_uri }
+
+    /// Returns the expiry time of the request URI.
+    pub fn expires(&self) -> SystemTime { self.expires }
+}
+
+impl FromResponse for PushedAuthorization {
+    fn from_response(json: &Value) -> Result<Self, ParseError> {
+        let obj = json.as_object().ok_or(ParseError::ExpectedType("object"))?;
+
+        let request_uri = obj.get("request_uri")
+            .and_then(Value::as_str)
+            .ok_or(ParseError::ExpectedFieldType("request_uri", "string"))?;
+        let expires_in = obj.get("expires_in")
+            .and_then(Value::as_u64)
+            .ok_or(ParseError::ExpectedFieldType("expires_in", "u64"))?;
+
+        Ok(PushedAuthorization {
+            request_uri: request_uri.into(),
+            expires: SystemTime::now() + Duration::from_secs(expires_in),
+        })
+    }
+}
+
+impl<P: Provider> Client<P> {
+    /// Pushes the parameters of an authorization request to the provider, authenticating the
+    /// client.
+    ///
+    /// The parameters are the same as those of [`Client::auth_uri_with`]. Build the authorization
+    /// URI from the response with [`Client::pushed_auth_uri`].
+    ///
+    /// See [RFC 9126, section 2.1](https://tools.ietf.org/html/rfc9126#section-2.1).
+    pub async fn push_authorization_request(
+        &self,
+        http_client: &impl HttpClient,
+        params: &AuthParams<'_>,
+    ) -> Result<PushedAuthorization, ClientError> {
+        let uri = self.provider.pushed_authorization_request_uri()
+            .ok_or(ClientError::MissingEndpoint("pushed authorization request"))?;
+
+        let body = {
+            // Serializer can't go across await points. See https://github.com/servo/rust-url/pull/550
+            let mut body = Serializer::new(String::new());
+            let needs_client_id = self.needs_client_id();
+            body.extend_pairs(
+                self.auth_request_params(params)
+                    .into_iter()
+                    .filter(|&(name, _)| needs_client_id || name != "client_id"),
+            );
+            body.finish()
+        };
+
+        let json = self.post_endpoint(http_client, uri, body).await?;
+        let pushed = PushedAuthorization::from_response(&json)?;
+        Ok(pushed)
+    }
+
+    /// Constructs an authorization request URI referencing pushed parameters, with only
+    /// `client_id` and `request_uri`.
+    ///
+    /// See [RFC 9126, section 4](https://tools.ietf.org/html/rfc9126#section-4).
+    pub fn pushed_auth_uri(&self, pushed: &PushedAuthorization) -> Url {
+        self.auth_uri_with_request_uri(&pushed.request_uri)
+    }
+
+    /// Pushes the parameters of an authorization request and returns the authorization request
+    /// URI referencing them.
+    ///
+    /// This is the pushed alternative to [`Client::auth_uri_with`].
+    pub async fn pushed_auth_uri_with(
+        &self,
+        http_client: &impl HttpClient,
+        params: &AuthParams<'_>,
+    ) -> Result<Url, ClientError> {
+        let pushed = self.push_authorization_request(http_client, params).await?;
+        Ok(self.pushed_auth_uri(&pushed))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use url::form_urlencoded;
+
+    use crate::client::http_client::{HttpResponse, AUTHORIZATION};
+    use crate::client::test_util::{MockHttp, Test};
+    use crate::client::ClientAuth;
+    use crate::error::{OAuth2Error, OAuth2ErrorCode};
+    use crate::jwt::{test_keys, PrivateKey};
+    use crate::pkce::Pkce;
+
+    fn client() -> Client<Test> {
+        Client::new(
+            Test::full(),
+            String::from("foo"),
+            String::from("bar"),
+            Some(String::from("https://client.example.com/cb")),
+        )
+    }
+
+    #[tokio::test]
+    async fn pushed_auth_uri_with() {
+        let http = MockHttp::with_responses(vec![HttpResponse {
+            status: 201,
+            headers: Vec::new(),
+            body: br#"
+                {
+                    "request_uri":"urn:ietf:params:oauth:request_uri:6esc_11ACC5bwc014ltc14eY22c",
+                    "expires_in":60
+                }
+            "#.to_vec(),
+        }]);
+        let pkce = Pkce::s256();
+
+        let uri = client()
+            .pushed_auth_uri_with(&http, &AuthParams {
+                scope: Some("baz"),
+                state: Some("af0ifjsldkj"),
+                pkce: Some(&pkce),
+                ..AuthParams::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(
+            "http://example.com/oauth2/auth?client_id=foo\
+             &request_uri=urn%3Aietf%3Aparams%3Aoauth%3Arequest_uri%3A6esc_11ACC5bwc014ltc14eY22c",
+            uri.as_str()
+        );
+
+        let request = &http.sent()[0];
+        assert_eq!("http://example.com/oauth2/par", request.url);
+        assert!(request.headers.iter().any(|(name, _)| *name == AUTHORIZATION));
+        assert_eq!(
+            format!(
+                "response_type=code&client_id=foo\
+                 &redirect_uri=https%3A%2F%2Fclient.example.com%2Fcb&scope=baz&state=af0ifjsldkj\
+                 &code_challenge={}&code_challenge_method=S256",
+                pkce.challenge()
+            ),
+            request.body
+        );
+    }
+
+    #[tokio::test]
+    async fn push_authorization_request_client_auth() {
+        let key = PrivateKey::from_pem(test_keys::EC_P256_PKCS8).unwrap();
+        let methods = vec![
+            ClientAuth::ClientSecretBasic,
+            ClientAuth::ClientSecretPost,
+            ClientAuth::None,
+            ClientAuth::ClientSecretJwt,
+            ClientAuth::private_key_jwt(key),
+        ];
+        for auth in methods {
+            let http = MockHttp::new(&[r#"{"request_uri":"urn:example:bwc4JK","expires_in":90}"#]);
+            let client = client().with_auth(auth.clone());
+            client.push_authorization_request(&http, &AuthParams::default()).await.unwrap();
+
+            let body = &http.requests()[0];
+            let client_ids = form_urlencoded::parse(body.as_bytes())
+                .filter(|(name, _)| name == "client_id")
+                .count();
+            assert_eq!(1, client_ids, "{:?}: {}", auth, body);
+        }
+    }
+
+    #[tokio::test]
+    async fn push_authorization_request_error() {
+        let http = MockHttp::new(&[r#"{"error":"invalid_request"}"#]);
+        assert!(matches!(
+            client().push_authorization_request(&http, &AuthParams::default()).await,
+            Err(ClientError::OAuth2(OAuth2Error { code: OAuth2ErrorCode::InvalidRequest, .. }))
+        ));
+    }
+
+    #[tokio::test]
+    async fn push_authorization_request_unsupported() {
+        let client = Client::new(Test::new(), String::from("foo"), String::from("bar"), None);
+        let http = MockHttp::new(&["{}"]);
+        assert!(matches!(
+            client.push_authorization_request(&http, &AuthParams::default()).await,
+            Err(ClientError::MissingEndpoint(_))
+        ));
+    }
+}
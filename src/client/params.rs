@@ -0,0 +1,103 @@
+//! Optional request parameters.
+
+use url::form_urlencoded::Serializer;
+
+use crate::oidc::Nonce;
+use crate::pkce::Pkce;
+use crate::rar::{self, AuthorizationDetail};
+
+/// Parameters of an authorization request.
+///
+/// See [RFC 6749, section 4.1.1](http://tools.ietf.org/html/rfc6749#section-4.1.1).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AuthParams<'a> {
+    /// Redirect URI for this request, instead of the client's.
+    pub redirect_uri: Option<&'a str>,
+
+    /// Requested scope.
+    pub scope: Option<&'a str>,
+
+    /// Opaque value used to maintain state between the request and callback.
+    pub state: Option<&'a str>,
+
+    /// PKCE verifier whose challenge is sent with the request.
+    pub pkce: Option<&'a Pkce>,
+
+    /// OpenID Connect nonce, to be checked against the ID token's `nonce` claim. Pass it again
+    /// as [`TokenParams::nonce`] to have the token request check it.
+    pub nonce: Option<&'a Nonce>,
+
+    /// Requested authorization details.
+    ///
+    /// See [RFC 9396, section 3](https://tools.ietf.org/html/rfc9396#section-3).
+    pub authorization_details: Option<&'a [AuthorizationDetail]>,
+
+    /// URIs of the resource servers where the tokens are to be used.
+    ///
+    /// See [RFC 8707, section 2.1](https://tools.ietf.org/html/rfc8707#section-2.1).
+    pub resource: &'a [&'a str],
+}
+
+/// Parameters of an authorization code token request.
+///
+/// See [RFC 6749, section 4.1.3](http://tools.ietf.org/html/rfc6749#section-4.1.3).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TokenParams<'a> {
+    /// Redirect URI of the authorization request, if it was not the client's.
+    pub redirect_uri: Option<&'a str>,
+
+    /// PKCE verifier used in the authorization request.
+    pub pkce: Option<&'a Pkce>,
+
+    /// OpenID Connect nonce sent in the authorization request. If set, the response must have an
+    /// ID token whose `nonce` claim equals it, or the request fails.
+    ///
+    /// Only the nonce is checked; verify the ID token's signature and other claims with
+    /// [`IdTokenVerifier`](crate::oidc::IdTokenVerifier).
+    pub nonce: Option<&'a Nonce>,
+
+    /// Authorization details requested for the token, a subset of those authorized.
+    ///
+    /// See [RFC 9396, section 6](https://tools.ietf.org/html/rfc9396#section-6).
+    pub authorization_details: Option<&'a [AuthorizationDetail]>,
+
+    /// URIs of the resource servers the access token is intended for, among those authorized.
+    ///
+    /// See [RFC 8707, section 2.2](https://tools.ietf.org/html/rfc8707#section-2.2).
+    pub resource: &'a [&'a str],
+}
+
+/// Parameters common to token requests other than the authorization code grant, such as client
+/// credentials, refresh, password, JWT bearer, token exchange and device authorization requests.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GrantParams<'a> {
+    /// Requested scope.
+    pub scope: Option<&'a str>,
+
+    /// Requested authorization details.
+    ///
+    /// See [RFC 9396, section 6](https://tools.ietf.org/html/rfc9396#section-6).
+    pub authorization_details: Option<&'a [AuthorizationDetail]>,
+
+    /// URIs of the resource servers the access token is intended for. When refreshing, a subset
+    /// of those originally authorized, such as a single one to obtain an audience-restricted
+    /// token.
+    ///
+    /// See [RFC 8707, section 2.2](https://tools.ietf.org/html/rfc8707#section-2.2).
+    pub resource: &'a [&'a str],
+}
+
+impl<'a> GrantParams<'a> {
+    /// Appends the parameters to a request body.
+    pub(crate) fn append_to(&self, body: &mut Serializer<String>) {
+        if let Some(scope) = self.scope {
+            body.append_pair("scope", scope);
+        }
+        if let Some(details) = self.authorization_details {
+            body.append_pair("authorization_details", &rar::to_param(details));
+        }
+        for resource in self.resource {
+            body.append_pair("resource", resource);
+        }
+    }
+}
@@ -0,0 +1,395 @@
+//! Dynamic client registration and management.
+//!
+//! See [RFC 7591](https://tools.ietf.org/html/rfc7591) and
+//! [RFC 7592](https://tools.ietf.org/html/rfc7592).
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde_json::{Map, Value};
+
+use crate::client::http_client::{HttpRequest, Method, ACCEPT};
+use crate::client::response::{FromResponse, ParseError};
+use crate::client::{Client, ClientAuth, ClientError, HttpClient};
+use crate::error::OAuth2Error;
+use crate::jwt::JwkSet;
+use crate::provider::Provider;
+
+/// Client metadata, registered with the provider.
+///
+/// Unset fields are omitted, leaving the provider to choose defaults.
+///
+/// See [RFC 7591, section 2](https://tools.ietf.org/html/rfc7591#section-2).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClientMetadata {
+    /// Redirection URIs.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub redirect_uris: Vec<String>,
+
+    /// Client authentication method at the token endpoint, such as `client_secret_basic` or
+    /// `none`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token_endpoint_auth_method: Option<String>,
+
+    /// Grant types the client may use, such as `authorization_code` and `refresh_token`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub grant_types: Vec<String>,
+
+    /// Response types the client may use, such as `code`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub response_types: Vec<String>,
+
+    /// Human-readable name of the client.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_name: Option<String>,
+
+    /// URL of the client's home page.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_uri: Option<String>,
+
+    /// URL of the client's logo.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub logo_uri: Option<String>,
+
+    /// Space-separated scopes the client may request.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+
+    /// Ways to contact the people responsible for the client, typically email addresses.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub contacts: Vec<String>,
+
+    /// URL of the client's JWK Set, for `private_key_jwt` and request objects.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub jwks_uri: Option<String>,
+
+    /// The client's JWK Set, passed by value. Mutually exclusive with `jwks_uri`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub jwks: Option<JwkSet>,
+
+    /// Identifier of the client software, the same for all its instances.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub software_id: Option<String>,
+
+    /// Version of the client software.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub software_version: Option<String>,
+
+    /// Signed JWT asserting metadata values about the client software.
+    ///
+    /// See [RFC 7591, section 2.3](https://tools.ietf.org/html/rfc7591#section-2.3).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub software_statement: Option<String>,
+
+    /// Other metadata, such as provider-specific fields.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Client information returned by the provider on registration.
+///
+/// See [RFC 7591, section 3.2.1](https://tools.ietf.org/html/rfc7591#section-3.2.1).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClientRegistration {
+    /// Client identifier.
+    pub client_id: String,
+
+    /// Client secret, if the client is confidential.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_secret: Option<String>,
+
+    /// Time the client identifier was issued, in seconds since the Unix epoch.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_id_issued_at: Option<u64>,
+
+    /// Time the client secret expires, in seconds since the Unix epoch, or 0 if it does not
+    /// expire. See [`ClientRegistration::client_secret_expires`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_secret_expires_at: Option<u64>,
+
+    /// Access token for reading, updating and deleting the registration.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub registration_access_token: Option<String>,
+
+    /// URI of the registration, for reading, updating and deleting it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub registration_client_uri: Option<String>,
+
+    /// Registered metadata, including values chosen by the provider.
+    #[serde(flatten)]
+    pub metadata: ClientMetadata,
+}
+
+impl FromResponse for ClientRegistration {
+    fn from_response(json: &Value) -> Result<Self, ParseError> {
+        let obj = json.as_object().ok_or(ParseError::ExpectedType("object"))?;
+        obj.get("client_id")
+            .and_then(Value::as_str)
+            .ok_or(ParseError::ExpectedFieldType("client_id", "string"))?;
+        serde_json::from_value(json.clone())
+            .map_err(|_| ParseError::ExpectedType("client information response"))
+    }
+}
+
+impl ClientRegistration {
+    /// Returns the expiry time of the client secret, or `None` if it does not expire.
+    pub fn client_secret_expires(&self) -> Option<SystemTime> {
+        match self.client_secret_expires_at {
+            None | Some(0) => None,
+            Some(secs) => Some(UNIX_EPOCH + Duration::from_secs(secs)),
+        }
+    }
+
+    /// Reads the current registration from the provider.
+    ///
+    /// See [RFC 7592, section 2.1](https://tools.ietf.org/html/rfc7592#section-2.1).
+    pub async fn read(&self, http_client: &impl HttpClient) -> Result<Self, ClientError> {
+        let request = self.management_request(Method::Get)?;
+        let json = send(http_client, request).await?;
+        let registration = ClientRegistration::from_response(&json)?;
+        Ok(registration)
+    }
+
+    /// Replaces the registered metadata. Omitted fields may be reset by the provider.
+    ///
+    /// See [RFC 7592, section 2.2](https://tools.ietf.org/html/rfc7592#section-2.2).
+    pub async fn update(
+        &self,
+        http_client: &impl HttpClient,
+        metadata: &ClientMetadata,
+    ) -> Result<Self, ClientError> {
+        let mut body = serde_json::to_value(metadata).expect("metadata serializes to JSON");
+        body["client_id"] = self.client_id.as_str().into();
+        if let Some(ref client_secret) = self.client_secret {
+            body["client_secret"] = client_secret.as_str().into();
+        }
+
+        let request = self.management_request(Method::Put)?.json(&body);
+        let json = send(http_client, request).await?;
+        let registration = ClientRegistration::from_response(&json)?;
+        Ok(registration)
+    }
+
+    /// Deletes the registration, invalidating the client identifier.
+    ///
+    /// See [RFC 7592, section 2.3](https://tools.ietf.org/html/rfc7592#section-2.3).
+    pub async fn delete(self, http_client: &impl HttpClient) -> Result<(), ClientError> {
+        let request = self.management_request(Method::Delete)?;
+        send(http_client, request).await?;
+        Ok(())
+    }
+
+    /// Creates a client for `provider` with the registered credentials.
+    ///
+    /// The redirect URI is the first registered one. Public clients have an empty secret. The
+    /// client authentication method is the registered one, except for `private_key_jwt`, whose
+    /// key must be set with [`Client::with_auth`].
+    pub fn into_client<P: Provider>(self, provider: P) -> Client<P> {
+        let auth = self.metadata.token_endpoint_auth_method.as_deref()
+            .and_then(ClientAuth::from_method);
+        let redirect_uri = self.metadata.redirect_uris.into_iter().next();
+        let client = Client::new(
+            provider,
+            self.client_id,
+            self.client_secret.unwrap_or_default(),
+            redirect_uri,
+        );
+        match auth {
+            Some(auth) => client.with_auth(auth),
+            None => client,
+        }
+    }
+
+    fn management_request(&self, method: Method) -> Result<HttpRequest, ClientError> {
+        let uri = self.registration_client_uri.as_ref()
+            .ok_or(ClientError::MissingEndpoint("client configuration"))?;
+        let token = self.registration_access_token.as_ref()
+            .ok_or(ClientError::MissingCredential("registration access token"))?;
+        Ok(HttpRequest::new(method, uri.as_str())
+            .bearer_auth(token)
+            .header(ACCEPT, "application/json"))
+    }
+}
+
+/// Registers a client with the provider's registration endpoint.
+///
+/// `initial_access_token` authorizes the registration at providers which do not allow open
+/// registration.
+///
+/// See [RFC 7591, section 3](https://tools.ietf.org/html/rfc7591#section-3).
+pub async fn register<P: Provider>(
+    http_client: &impl HttpClient,
+    provider: &P,
+    metadata: &ClientMetadata,
+    initial_access_token: Option<&str>,
+) -> Result<ClientRegistration, ClientError> {
+    let uri = provider.registration_uri()
+        .ok_or(ClientError::MissingEndpoint("registration"))?;
+
+    let body = serde_json::to_value(metadata).expect("metadata serializes to JSON");
+    let mut request = HttpRequest::new(Method::Post, uri.as_str())
+        .header(ACCEPT, "application/json")
+        .json(&body);
+    if let Some(token) = initial_access_token {
+        request = request.bearer_auth(token);
+    }
+
+    let json = send(http_client, request).await?;
+    let registration = ClientRegistration::from_response(&json)?;
+    Ok(registration)
+}
+
+/// Sends a registration request. Error responses are `ClientError::OAuth2` errors if their body
+/// is an OAuth 2.0 error, and `ClientError::HttpStatus` errors otherwise.
+async fn send(
+    http_client: &impl HttpClient,
+    request: HttpRequest,
+) -> Result<Value, ClientError> {
+    let response = http_client.send(request).await?;
+    if !response.is_success() {
+        let error = response.json().ok().and_then(|json| OAuth2Error::from_response(&json).ok());
+        return Err(match error {
+            Some(error) => error.into(),
+            None => ClientError::HttpStatus(response.status),
+        });
+    }
+    response.json()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::client::http_client::{HttpResponse, AUTHORIZATION};
+    use crate::client::test_util::{MockHttp, Test};
+    use crate::error::OAuth2ErrorCode;
+
+    const RESPONSE: &str = r#"
+        {
+            "client_id": "s6BhdRkqt3",
+            "client_secret": "cf136dc3c1fc93f31185e5885805d",
+            "client_id_issued_at": 2893256800,
+            "client_secret_expires_at": 0,
+            "registration_access_token": "this.is.an.access.token.value.ffx83",
+            "registration_client_uri": "https://server.example.com/register/s6BhdRkqt3",
+            "redirect_uris": ["https://client.example.org/callback"],
+            "token_endpoint_auth_method": "client_secret_basic",
+            "client_name": "My Example Client",
+            "logo_uri": "https://client.example.org/logo.png"
+        }
+    "#;
+
+    fn response(status: u16, body: &str) -> HttpResponse {
+        HttpResponse { status, headers: Vec::new(), body: body.as_bytes().to_vec() }
+    }
+
+    fn metadata() -> ClientMetadata {
+        ClientMetadata {
+            redirect_uris: vec![String::from("https://client.example.org/callback")],
+            client_name: Some(String::from("My Example Client")),
+            ..ClientMetadata::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn register() {
+        let http = MockHttp::with_responses(vec![response(201, RESPONSE)]);
+        let registration = super::register(&http, &Test::full(), &metadata(), Some("iat"))
+            .await
+            .unwrap();
+        assert_eq!("s6BhdRkqt3", registration.client_id);
+        assert_eq!(None, registration.client_secret_expires());
+        assert_eq!(
+            Some("client_secret_basic"),
+            registration.metadata.token_endpoint_auth_method.as_deref()
+        );
+        assert_eq!(
+            Some("https://client.example.org/logo.png"),
+            registration.metadata.logo_uri.as_deref()
+        );
+
+        let request = &http.sent()[0];
+        assert_eq!(Method::Post, request.method);
+        assert_eq!("http://example.com/oauth2/register", request.url);
+        assert!(request.headers.contains(&(AUTHORIZATION, String::from("Bearer iat"))));
+        assert_eq!(
+            r#"{"client_name":"My Example Client","redirect_uris":["https://client.example.org/callback"]}"#,
+            request.body
+        );
+
+        let client = registration.into_client(Test::new());
+        assert_eq!("client_secret_basic", client.auth.method());
+        assert_eq!("s6BhdRkqt3", client.client_id);
+        assert_eq!("cf136dc3c1fc93f31185e5885805d", client.client_secret);
+        assert_eq!(Some("https://client.example.org/callback"), client.redirect_uri.as_deref());
+    }
+
+    #[tokio::test]
+    async fn register_error() {
+        let http = MockHttp::with_responses(vec![response(
+            400,
+            r#"{"error":"invalid_redirect_uri","error_description":"Bad URI"}"#,
+        )]);
+        match super::register(&http, &Test::full(), &metadata(), None).await {
+            Err(ClientError::OAuth2(err)) => {
+                assert_eq!(OAuth2ErrorCode::InvalidRedirectUri, err.code)
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+
+        let http = MockHttp::with_responses(vec![response(401, "")]);
+        assert!(matches!(
+            super::register(&http, &Test::full(), &metadata(), None).await,
+            Err(ClientError::HttpStatus(401))
+        ));
+        assert!(matches!(
+            super::register(&http, &Test::new(), &metadata(), None).await,
+            Err(ClientError::MissingEndpoint("registration"))
+        ));
+    }
+
+    #[tokio::test]
+    async fn manage() {
+        let registration =
+            ClientRegistration::from_response(&RESPONSE.parse().unwrap()).unwrap();
+        let http = MockHttp::with_responses(vec![
+            response(200, RESPONSE),
+            response(200, RESPONSE),
+            response(204, ""),
+        ]);
+
+        registration.read(&http).await.unwrap();
+        let metadata = ClientMetadata { client_name: Some(String::from("New")), ..metadata() };
+        registration.update(&http, &metadata).await.unwrap();
+        registration.delete(&http).await.unwrap();
+
+        let sent = http.sent();
+        let methods: Vec<_> = sent.iter().map(|request| request.method).collect();
+        assert_eq!(vec![Method::Get, Method::Put, Method::Delete], methods);
+        for request in &sent {
+            assert_eq!("https://server.example.com/register/s6BhdRkqt3", request.url);
+            assert!(request.headers.contains(&(
+                AUTHORIZATION,
+                String::from("Bearer this.is.an.access.token.value.ffx83"),
+            )));
+        }
+        let body: Value = serde_json::from_str(&sent[1].body).unwrap();
+        assert_eq!("s6BhdRkqt3", body["client_id"]);
+        assert_eq!("cf136dc3c1fc93f31185e5885805d", body["client_secret"]);
+        assert_eq!("New", body["client_name"]);
+    }
+
+    #[tokio::test]
+    async fn manage_without_access_token() {
+        let registration = ClientRegistration {
+            registration_access_token: None,
+            ..ClientRegistration::from_response(&RESPONSE.parse().unwrap()).unwrap()
+        };
+        let http = MockHttp::new(&[RESPONSE]);
+
+        assert!(matches!(
+            registration.read(&http).await,
+            Err(ClientError::MissingCredential("registration access token"))
+        ));
+        assert!(http.sent().is_empty());
+    }
+}
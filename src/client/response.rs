@@ -34,6 +34,9 @@ pub enum ParseError {
 
     /// Expected field to not be present.
     UnexpectedField(&'static str),
+
+    /// Expected field to be present at most once.
+    DuplicateField(&'static str),
 }
 
 impl fmt::Display for ParseError {
@@ -47,6 +50,8 @@ impl fmt::Display for ParseError {
                 write!(f, "Expected field {} to equal {}", k, v),
             ParseError::UnexpectedField(k) =>
                 write!(f, "Unexpected field {}", k),
+            ParseError::DuplicateField(k) =>
+                write!(f, "Duplicate field {}", k),
         }
     }
 }
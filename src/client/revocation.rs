@@ -0,0 +1,192 @@
+//! Token revocation.
+//!
+//! See [RFC 7009](https://tools.ietf.org/html/rfc7009).
+
+use url::form_urlencoded::Serializer;
+
+use crate::client::http_client::HttpResponse;
+use crate::client::response::FromResponse;
+use crate::client::{Client, ClientError, HttpClient};
+use crate::error::OAuth2Error;
+use crate::provider::{GitHub, Provider};
+use crate::token::{Refresh, Token};
+
+impl<P: Provider> Client<P> {
+    /// Revokes a token.
+    ///
+    /// `token_type_hint` is `access_token`, `refresh_token`, or an extension token type.
+    ///
+    /// See [RFC 7009, section 2.1](https://tools.ietf.org/html/rfc7009#section-2.1).
+    pub async fn revoke(
+        &self,
+        http_client: &impl HttpClient,
+        token: &str,
+        token_type_hint: Option<&str>,
+    ) -> Result<(), ClientError> {
+        let uri = self.provider.revocation_uri()
+            .ok_or(ClientError::MissingEndpoint("revocation"))?;
+
+        let body = {
+            // Serializer can't go across await points. See https://github.com/servo/rust-url/pull/550
+            let mut body = Serializer::new(String::new());
+            body.append_pair("token", token);
+            if let Some(token_type_hint) = token_type_hint {
+                body.append_pair("token_type_hint", token_type_hint);
+            }
+            body.finish()
+        };
+
+        let request = self.authenticated_request(uri, body).await?;
+
+        revocation_result(http_client.send(request).await?)
+    }
+
+    /// Revokes the access token of a token.
+    ///
+    /// Depending on the provider, this may also revoke the refresh token.
+    pub async fn revoke_token(
+        &self,
+        http_client: &impl HttpClient,
+        token: &P::Token,
+    ) -> Result<(), ClientError> {
+        self.revoke(http_client, token.access_token(), Some("access_token")).await
+    }
+}
+
+impl<P> Client<P> where P: Provider, P::Token: Token<Refresh> {
+    /// Revokes the refresh token of a token.
+    ///
+    /// Providers also revoke access tokens issued with the refresh token, if they can.
+    pub async fn revoke_refresh_token(
+        &self,
+        http_client: &impl HttpClient,
+        token: &P::Token,
+    ) -> Result<(), ClientError> {
+        let refresh_token = token.lifetime().refresh_token();
+        self.revoke(http_client, refresh_token, Some("refresh_token")).await
+    }
+}
+
+impl Client<GitHub> {
+    /// Revokes a token with GitHub's application API, as GitHub has no RFC 7009 endpoint.
+    ///
+    /// The API uses HTTP Basic authentication, whatever the client authentication method.
+    /// See [`GitHub::revocation_request`].
+    pub async fn revoke_github_token(
+        &self,
+        http_client: &impl HttpClient,
+        token: &str,
+    ) -> Result<(), ClientError> {
+        let request = self.provider.revocation_request(&self.client_id, &self.client_secret, token);
+        revocation_result(http_client.send(request).await?)
+    }
+}
+
+fn revocation_result(response: HttpResponse) -> Result<(), ClientError> {
+    if response.is_success() {
+        return Ok(());
+    }
+    match response.json().map(|json| OAuth2Error::from_response(&json)) {
+        Ok(Ok(error)) => Err(ClientError::from(error)),
+        _ => Err(ClientError::HttpStatus(response.status)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::client::http_client::{Method, AUTHORIZATION};
+    use crate::client::ClientAuth;
+    use crate::client::test_util::{MockHttp, Test};
+    use crate::error::OAuth2ErrorCode;
+    use crate::provider::GitHub;
+    use crate::token::{Bearer, Static};
+
+    fn client() -> Client<Test> {
+        Client::new(Test::full(), String::from("foo"), String::from("bar"), None)
+    }
+
+    fn token() -> Bearer<Static> {
+        let json = r#"{"token_type":"Bearer","access_token":"aaaaaaaa"}"#.parse().unwrap();
+        Bearer::from_response(&json).unwrap()
+    }
+
+    #[tokio::test]
+    async fn revoke_token() {
+        let http = MockHttp::new(&[""]);
+        client().revoke_token(&http, &token()).await.unwrap();
+        assert_eq!(vec!["http://example.com/oauth2/revoke"], http.urls());
+        assert_eq!(vec!["token=aaaaaaaa&token_type_hint=access_token"], http.requests());
+    }
+
+    #[tokio::test]
+    async fn revoke_unsupported_token_type() {
+        let http = MockHttp::with_responses(vec![HttpResponse {
+            status: 400,
+            headers: Vec::new(),
+            body: br#"{"error":"unsupported_token_type"}"#.to_vec(),
+        }]);
+        assert!(matches!(
+            client().revoke_token(&http, &token()).await,
+            Err(ClientError::OAuth2(OAuth2Error { code: OAuth2ErrorCode::UnsupportedTokenType, .. }))
+        ));
+    }
+
+    #[tokio::test]
+    async fn revoke_unavailable() {
+        let http = MockHttp::with_responses(vec![HttpResponse {
+            status: 503,
+            headers: Vec::new(),
+            body: b"<html></html>".to_vec(),
+        }]);
+        assert!(matches!(
+            client().revoke_token(&http, &token()).await,
+            Err(ClientError::HttpStatus(503))
+        ));
+    }
+
+    #[tokio::test]
+    async fn revoke_unsupported() {
+        let client = Client::new(Test::new(), String::from("foo"), String::from("bar"), None);
+        let http = MockHttp::new(&[""]);
+        assert!(matches!(
+            client.revoke_token(&http, &token()).await,
+            Err(ClientError::MissingEndpoint(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn revoke_client_auth() {
+        let http = MockHttp::new(&[""]);
+        let client = client().with_auth(ClientAuth::ClientSecretPost);
+        client.revoke(&http, "aaaaaaaa", None).await.unwrap();
+
+        let request = http.sent().remove(0);
+        assert!(request.headers.iter().all(|(name, _)| *name != AUTHORIZATION));
+        assert_eq!("token=aaaaaaaa&client_id=foo&client_secret=bar", request.body);
+    }
+
+    #[tokio::test]
+    async fn revoke_github() {
+        let client = Client::new(GitHub, String::from("foo"), String::from("bar"), None)
+            .with_auth(ClientAuth::ClientSecretPost);
+        let http = MockHttp::with_responses(vec![HttpResponse {
+            status: 204,
+            headers: Vec::new(),
+            body: Vec::new(),
+        }]);
+        client.revoke_github_token(&http, "aaaaaaaa").await.unwrap();
+
+        let request = http.sent().remove(0);
+        assert_eq!(Method::Delete, request.method);
+        assert_eq!("https://api.github.com/applications/foo/token", request.url);
+        assert!(request.headers.contains(&(AUTHORIZATION, String::from("Basic Zm9vOmJhcg=="))));
+        assert_eq!(r#"{"access_token":"aaaaaaaa"}"#, request.body);
+
+        assert!(matches!(
+            client.revoke_token(&http, &token()).await,
+            Err(ClientError::MissingEndpoint(_))
+        ));
+    }
+}
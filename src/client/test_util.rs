@@ -0,0 +1,146 @@
+//! Test providers and a mock HTTP client.
+
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+use url::Url;
+
+use crate::client::http_client::{HttpRequest, HttpResponse};
+use crate::client::{ClientError, HttpClient};
+use crate::provider::Provider;
+use crate::token::{Bearer, Expiring, Refresh, Static};
+
+pub struct Test {
+    pub issuer: Option<String>,
+    pub iss_parameter_supported: bool,
+    pub auth_uri: Url,
+    pub token_uri: Url,
+    pub registration_uri: Option<Url>,
+    pub pushed_authorization_request_uri: Option<Url>,
+    pub device_authorization_uri: Option<Url>,
+    pub revocation_uri: Option<Url>,
+    pub introspection_uri: Option<Url>,
+    pub userinfo_uri: Option<Url>,
+}
+impl Provider for Test {
+    type Lifetime = Static;
+    type Token = Bearer<Static>;
+    fn issuer(&self) -> Option<&str> { self.issuer.as_deref() }
+    fn authorization_response_iss_parameter_supported(&self) -> bool {
+        self.iss_parameter_supported
+    }
+    fn auth_uri(&self) -> &Url { &self.auth_uri }
+    fn token_uri(&self) -> &Url { &self.token_uri }
+    fn registration_uri(&self) -> Option<&Url> { self.registration_uri.as_ref() }
+    fn pushed_authorization_request_uri(&self) -> Option<&Url> {
+        self.pushed_authorization_request_uri.as_ref()
+    }
+    fn device_authorization_uri(&self) -> Option<&Url> { self.device_authorization_uri.as_ref() }
+    fn revocation_uri(&self) -> Option<&Url> { self.revocation_uri.as_ref() }
+    fn introspection_uri(&self) -> Option<&Url> { self.introspection_uri.as_ref() }
+    fn userinfo_uri(&self) -> Option<&Url> { self.userinfo_uri.as_ref() }
+}
+impl Test {
+    /// Only the authorization and token endpoints.
+    pub fn new() -> Self {
+        Test {
+            issuer: None,
+            iss_parameter_supported: false,
+            auth_uri: Url::parse("http://example.com/oauth2/auth").unwrap(),
+            token_uri: Url::parse("http://example.com/oauth2/token").unwrap(),
+            registration_uri: None,
+            pushed_authorization_request_uri: None,
+            device_authorization_uri: None,
+            revocation_uri: None,
+            introspection_uri: None,
+            userinfo_uri: None,
+        }
+    }
+
+    /// Every endpoint, at `http://example.com/oauth2/<name>`.
+    pub fn full() -> Self {
+        let uri = |name: &str| Some(Url::parse("http://example.com/oauth2/").unwrap().join(name).unwrap());
+        Test {
+            registration_uri: uri("register"),
+            pushed_authorization_request_uri: uri("par"),
+            device_authorization_uri: uri("device"),
+            revocation_uri: uri("revoke"),
+            introspection_uri: uri("introspect"),
+            userinfo_uri: uri("userinfo"),
+            ..Test::new()
+        }
+    }
+}
+
+pub struct TestExpiring(pub Test);
+impl Provider for TestExpiring {
+    type Lifetime = Expiring;
+    type Token = Bearer<Expiring>;
+    fn auth_uri(&self) -> &Url { &self.0.auth_uri }
+    fn token_uri(&self) -> &Url { &self.0.token_uri }
+}
+
+pub struct TestRefresh(pub Test);
+impl Provider for TestRefresh {
+    type Lifetime = Refresh;
+    type Token = Bearer<Refresh>;
+    fn auth_uri(&self) -> &Url { &self.0.auth_uri }
+    fn token_uri(&self) -> &Url { &self.0.token_uri }
+}
+
+/// Records requests and replies with canned responses, in order. The last response is repeated.
+pub struct MockHttp {
+    responses: Mutex<VecDeque<HttpResponse>>,
+    requests: Mutex<Vec<HttpRequest>>,
+}
+impl MockHttp {
+    /// Replies with status 200 and each of the given bodies.
+    pub fn new(responses: &[&str]) -> Self {
+        let responses = responses.iter()
+            .map(|body| HttpResponse {
+                status: 200,
+                headers: Vec::new(),
+                body: body.as_bytes().to_vec(),
+            })
+            .collect();
+        MockHttp::with_responses(responses)
+    }
+
+    pub fn with_responses(responses: Vec<HttpResponse>) -> Self {
+        MockHttp {
+            responses: Mutex::new(responses.into()),
+            requests: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Requests sent, in order.
+    pub fn sent(&self) -> Vec<HttpRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    /// Request bodies, in order.
+    pub fn requests(&self) -> Vec<String> {
+        self.sent().into_iter().map(|request| request.body).collect()
+    }
+
+    /// Request URLs, in order.
+    pub fn urls(&self) -> Vec<String> {
+        self.sent().into_iter().map(|request| request.url).collect()
+    }
+
+    fn respond(&self) -> HttpResponse {
+        let mut responses = self.responses.lock().unwrap();
+        if responses.len() > 1 {
+            responses.pop_front().unwrap()
+        } else {
+            responses.front().cloned().expect("no response")
+        }
+    }
+}
+#[async_trait::async_trait]
+impl HttpClient for MockHttp {
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse, ClientError> {
+        self.requests.lock().unwrap().push(request);
+        Ok(self.respond())
+    }
+}
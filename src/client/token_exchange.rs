@@ -0,0 +1,263 @@
+//! OAuth 2.0 Token Exchange.
+//!
+//! See [RFC 8693](https://tools.ietf.org/html/rfc8693).
+
+use std::time::{Duration, SystemTime};
+
+use serde_json::Value;
+use url::form_urlencoded::Serializer;
+
+use crate::client::response::{FromResponse, ParseError};
+use crate::client::{Client, ClientError, GrantParams, HttpClient};
+use crate::provider::Provider;
+
+/// Token type identifiers.
+///
+/// See [RFC 8693, section 3](https://tools.ietf.org/html/rfc8693#section-3).
+pub mod token_type {
+    /// An OAuth 2.0 access token.
+    pub const ACCESS_TOKEN: &str = "urn:ietf:params:oauth:token-type:access_token";
+
+    /// An OAuth 2.0 refresh token.
+    pub const REFRESH_TOKEN: &str = "urn:ietf:params:oauth:token-type:refresh_token";
+
+    /// An OpenID Connect ID token.
+    pub const ID_TOKEN: &str = "urn:ietf:params:oauth:token-type:id_token";
+
+    /// A base64url-encoded SAML 1.1 assertion.
+    pub const SAML1: &str = "urn:ietf:params:oauth:token-type:saml1";
+
+    /// A base64url-encoded SAML 2.0 assertion.
+    pub const SAML2: &str = "urn:ietf:params:oauth:token-type:saml2";
+
+    /// A JWT.
+    pub const JWT: &str = "urn:ietf:params:oauth:token-type:jwt";
+}
+
+/// Parameters of a token exchange request.
+///
+/// See [RFC 8693, section 2.1](https://tools.ietf.org/html/rfc8693#section-2.1).
+#[derive(Debug, Clone, Copy)]
+pub struct TokenExchangeParams<'a> {
+    /// The token representing the party on behalf of whom the request is made.
+    pub subject_token: &'a str,
+
+    /// The type of `subject_token`, one of the [`token_type`] identifiers.
+    pub subject_token_type: &'a str,
+
+    /// A token representing the acting party, and its type.
+    pub actor_token: Option<(&'a str, &'a str)>,
+
+    /// The type of token requested.
+    pub requested_token_type: Option<&'a str>,
+
+    /// Logical names of the target services. Their URIs are the `resource` of the
+    /// [`GrantParams`] sent alongside.
+    pub audience: &'a [&'a str],
+}
+
+impl<'a> TokenExchangeParams<'a> {
+    /// Creates parameters with only the required subject token.
+    pub fn new(subject_token: &'a str, subject_token_type: &'a str) -> Self {
+        TokenExchangeParams {
+            subject_token,
+            subject_token_type,
+            actor_token: None,
+            requested_token_type: None,
+            audience: &[],
+        }
+    }
+}
+
+/// A token issued by a token exchange.
+///
+/// This is the supported way to receive exchanged tokens, rather than the provider's token type:
+/// the issued token may be an ID token or other non-access token with a `token_type` of `N_A`,
+/// which is not a valid access token response, and the response need not have the `expires_in`
+/// or `refresh_token` the provider's lifetime requires. Convert it as needed.
+///
+/// See [RFC 8693, section 2.2.1](https://tools.ietf.org/html/rfc8693#section-2.2.1).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExchangedToken {
+    /// The issued token, sent as `access_token` whatever its type.
+    pub access_token: String,
+
+    /// The type of the issued token, one of the [`token_type`] identifiers.
+    pub issued_token_type: String,
+
+    /// How to use the issued token, usually `Bearer`, or `N_A` if it is not an access token.
+    pub token_type: String,
+
+    /// The expiry time of the issued token, if given.
+    pub expires: Option<SystemTime>,
+
+    /// The scope of the issued token, if it differs from the requested scope.
+    pub scope: Option<String>,
+
+    /// A refresh token, if issued.
+    pub refresh_token: Option<String>,
+}
+
+impl FromResponse for ExchangedToken {
+    fn from_response(json: &Value) -> Result<Self, ParseError> {
+        let obj = json.as_object().ok_or(ParseError::ExpectedType("object"))?;
+
+        let string = |key| match obj.get(key) {
+            None => Ok(None),
+            Some(Value::String(value)) => Ok(Some(value.clone())),
+            Some(_) => Err(ParseError::ExpectedFieldType(key, "string")),
+        };
+        let access_token = string("access_token")?
+            .ok_or(ParseError::ExpectedFieldType("access_token", "string"))?;
+        let issued_token_type = string("issued_token_type")?
+            .ok_or(ParseError::ExpectedFieldType("issued_token_type", "string"))?;
+        let token_type = string("token_type")?
+            .ok_or(ParseError::ExpectedFieldType("token_type", "string"))?;
+        let expires = match obj.get("expires_in") {
+            None => None,
+            Some(expires_in) => {
+                let expires_in = expires_in.as_u64()
+                    .ok_or(ParseError::ExpectedFieldType("expires_in", "u64"))?;
+                Some(SystemTime::now() + Duration::from_secs(expires_in))
+            }
+        };
+
+        Ok(ExchangedToken {
+            access_token,
+            issued_token_type,
+            token_type,
+            expires,
+            scope: string("scope")?,
+            refresh_token: string("refresh_token")?,
+        })
+    }
+}
+
+impl<P: Provider> Client<P> {
+    /// Exchanges a security token for another.
+    ///
+    /// `params` carries the requested scope and target resources. The response is parsed as an
+    /// [`ExchangedToken`] rather than the provider's token type; tokens which are not access
+    /// tokens are returned with a `token_type` of `N_A`.
+    ///
+    /// See [RFC 8693, section 2](https://tools.ietf.org/html/rfc8693#section-2).
+    pub async fn exchange_token(
+        &self,
+        http_client: &impl HttpClient,
+        exchange: &TokenExchangeParams<'_>,
+        params: &GrantParams<'_>,
+    ) -> Result<ExchangedToken, ClientError> {
+        let body = {
+            // Serializer can't go across await points. See https://github.com/servo/rust-url/pull/550
+            let mut body = Serializer::new(String::new());
+            body.append_pair("grant_type", "urn:ietf:params:oauth:grant-type:token-exchange");
+            body.append_pair("subject_token", exchange.subject_token);
+            body.append_pair("subject_token_type", exchange.subject_token_type);
+
+            if let Some((actor_token, actor_token_type)) = exchange.actor_token {
+                body.append_pair("actor_token", actor_token);
+                body.append_pair("actor_token_type", actor_token_type);
+            }
+            if let Some(requested_token_type) = exchange.requested_token_type {
+                body.append_pair("requested_token_type", requested_token_type);
+            }
+            for audience in exchange.audience {
+                body.append_pair("audience", audience);
+            }
+            params.append_to(&mut body);
+
+            body.finish()
+        };
+
+        let json = self.post_token(http_client, body).await?;
+        let token = ExchangedToken::from_response(&json)?;
+        Ok(token)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::client::test_util::{MockHttp, Test, TestRefresh};
+
+    #[tokio::test]
+    async fn exchange_token() {
+        let client = Client::new(Test::new(), String::from("foo"), String::from("bar"), None);
+        let http = MockHttp::new(&[r#"
+            {
+                "access_token":"aaaaaaaa",
+                "issued_token_type":"urn:ietf:params:oauth:token-type:access_token",
+                "token_type":"Bearer"
+            }
+        "#]);
+
+        let token = client
+            .exchange_token(
+                &http,
+                &TokenExchangeParams {
+                    actor_token: Some(("cccccccc", token_type::JWT)),
+                    audience: &["svc"],
+                    ..TokenExchangeParams::new("bbbbbbbb", token_type::ACCESS_TOKEN)
+                },
+                &GrantParams {
+                    scope: Some("read"),
+                    resource: &["https://example.com/api"],
+                    ..GrantParams::default()
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!("aaaaaaaa", token.access_token);
+        assert_eq!(token_type::ACCESS_TOKEN, token.issued_token_type);
+        assert_eq!("Bearer", token.token_type);
+        assert_eq!(None, token.expires);
+        assert_eq!(
+            vec![
+                "grant_type=urn%3Aietf%3Aparams%3Aoauth%3Agrant-type%3Atoken-exchange\
+                 &subject_token=bbbbbbbb\
+                 &subject_token_type=urn%3Aietf%3Aparams%3Aoauth%3Atoken-type%3Aaccess_token\
+                 &actor_token=cccccccc\
+                 &actor_token_type=urn%3Aietf%3Aparams%3Aoauth%3Atoken-type%3Ajwt\
+                 &audience=svc&scope=read\
+                 &resource=https%3A%2F%2Fexample.com%2Fapi"
+            ],
+            http.requests()
+        );
+    }
+
+    #[tokio::test]
+    async fn exchange_token_not_applicable() {
+        let client = Client::new(
+            TestRefresh(Test::new()),
+            String::from("foo"),
+            String::from("bar"),
+            None,
+        );
+        let http = MockHttp::new(&[r#"
+            {
+                "access_token":"aaaaaaaa",
+                "issued_token_type":"urn:ietf:params:oauth:token-type:id_token",
+                "token_type":"N_A",
+                "expires_in":60
+            }
+        "#]);
+
+        let token = client
+            .exchange_token(
+                &http,
+                &TokenExchangeParams {
+                    requested_token_type: Some(token_type::ID_TOKEN),
+                    ..TokenExchangeParams::new("bbbbbbbb", token_type::ACCESS_TOKEN)
+                },
+                &GrantParams::default(),
+            )
+            .await
+            .unwrap();
+        assert_eq!("aaaaaaaa", token.access_token);
+        assert_eq!(token_type::ID_TOKEN, token.issued_token_type);
+        assert_eq!("N_A", token.token_type);
+        assert!(token.expires.unwrap() > SystemTime::now());
+        assert_eq!(None, token.refresh_token);
+    }
+}
@@ -0,0 +1,198 @@
+//! OpenID Connect UserInfo endpoint.
+//!
+//! See [OpenID Connect Core 1.0, section 5.3](https://openid.net/specs/openid-connect-core-1_0.html#UserInfo).
+
+use serde_json::Value;
+
+use crate::client::http_client::{HttpRequest, Method, ACCEPT};
+use crate::client::{Client, ClientError, HttpClient};
+use crate::jwt::{self, JwkSet, JwtError};
+use crate::oidc::{IdTokenClaims, StandardClaims};
+use crate::provider::Provider;
+use crate::token::Token;
+
+/// Parameters of a UserInfo request.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UserInfoParams<'a> {
+    /// Keys to verify signed (`application/jwt`) responses with. Signed responses are rejected
+    /// without them.
+    pub keys: Option<&'a JwkSet>,
+
+    /// Claims of the validated ID token, which the response's `sub` claim must match.
+    pub id_token: Option<&'a IdTokenClaims>,
+}
+
+impl<P: Provider> Client<P> {
+    /// Requests claims about the end-user authenticated by `token`.
+    ///
+    /// See [`Client::userinfo_with`] to accept signed responses and to check the subject.
+    pub async fn userinfo(
+        &self,
+        http_client: &impl HttpClient,
+        token: &P::Token,
+    ) -> Result<StandardClaims, ClientError> {
+        self.userinfo_with(http_client, token, &UserInfoParams::default()).await
+    }
+
+    /// Requests claims about the end-user authenticated by `token`.
+    ///
+    /// Signed responses are verified with `params.keys`; their `iss` and `aud` claims, if
+    /// present, must match the ID token and client. If `params.id_token` is set, the `sub` claim
+    /// must equal the ID token's, as the claims could otherwise be of another user.
+    ///
+    /// See [OpenID Connect Core 1.0, section 5.3.2](https://openid.net/specs/openid-connect-core-1_0.html#UserInfoResponse).
+    pub async fn userinfo_with(
+        &self,
+        http_client: &impl HttpClient,
+        token: &P::Token,
+        params: &UserInfoParams<'_>,
+    ) -> Result<StandardClaims, ClientError> {
+        let uri = self.provider.userinfo_uri()
+            .ok_or(ClientError::MissingEndpoint("userinfo"))?;
+
+        let request = HttpRequest::new(Method::Get, uri.as_str())
+            .bearer_auth(token.access_token())
+            .header(ACCEPT, "application/json, application/jwt");
+        let response = http_client.send(request).await?;
+        if !response.is_success() {
+            return Err(ClientError::HttpStatus(response.status));
+        }
+
+        let is_jwt = response.header("Content-Type")
+            .is_some_and(|content_type| content_type.starts_with("application/jwt"));
+        let json = if is_jwt {
+            let keys = params.keys.ok_or(JwtError::UnknownKey)?;
+            let jwt = String::from_utf8_lossy(&response.body);
+            let claims = jwt::decode(jwt.trim(), keys)?;
+
+            if let (Some(iss), Some(id_token)) = (claims.get("iss"), params.id_token) {
+                if *iss != *id_token.iss {
+                    return Err(JwtError::InvalidClaim("iss").into());
+                }
+            }
+            let audience_matches = match claims.get("aud") {
+                None => true,
+                Some(Value::Array(auds)) => auds.iter().any(|aud| *aud == *self.client_id),
+                Some(aud) => *aud == *self.client_id,
+            };
+            if !audience_matches {
+                return Err(JwtError::InvalidClaim("aud").into());
+            }
+            claims
+        } else {
+            response.json()?
+        };
+
+        let claims: StandardClaims = serde_json::from_value(json)?;
+        if let Some(id_token) = params.id_token {
+            if claims.sub != id_token.standard.sub {
+                return Err(JwtError::InvalidClaim("sub").into());
+            }
+        }
+        Ok(claims)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    use crate::client::http_client::{HttpResponse, AUTHORIZATION};
+    use crate::client::response::FromResponse;
+    use crate::client::test_util::{MockHttp, Test};
+    use crate::jwt::{test_keys, PrivateKey};
+    use crate::token::{Bearer, Static};
+
+    fn client() -> Client<Test> {
+        Client::new(Test::full(), String::from("foo"), String::from("bar"), None)
+    }
+
+    fn token() -> Bearer<Static> {
+        Bearer::from_response(&json!({"token_type": "Bearer", "access_token": "aaaaaaaa"})).unwrap()
+    }
+
+    fn id_token(sub: &str) -> IdTokenClaims {
+        serde_json::from_value(json!({
+            "iss": "https://example.com",
+            "aud": "foo",
+            "exp": 0,
+            "iat": 0,
+            "sub": sub,
+        })).unwrap()
+    }
+
+    const USERINFO: &str = r#"
+        {
+            "sub": "248289761001",
+            "name": "Jane Doe",
+            "email": "janedoe@example.com",
+            "email_verified": true,
+            "picture": "http://example.com/janedoe/me.jpg",
+            "locale": "en",
+            "hd": "example.com"
+        }
+    "#;
+
+    #[tokio::test]
+    async fn userinfo() {
+        let http = MockHttp::new(&[USERINFO]);
+        let claims = client().userinfo(&http, &token()).await.unwrap();
+        assert_eq!("248289761001", claims.sub);
+        assert_eq!(Some("Jane Doe"), claims.name.as_ref().map(|s| &s[..]));
+        assert_eq!(Some(true), claims.email_verified);
+        assert_eq!(Some("en"), claims.locale.as_ref().map(|s| &s[..]));
+        assert_eq!(Some(&json!("example.com")), claims.extra.get("hd"));
+
+        let request = &http.sent()[0];
+        assert_eq!(Method::Get, request.method);
+        assert_eq!("http://example.com/oauth2/userinfo", request.url);
+        assert!(request.headers.contains(&(AUTHORIZATION, String::from("Bearer aaaaaaaa"))));
+    }
+
+    #[tokio::test]
+    async fn userinfo_subject_mismatch() {
+        let http = MockHttp::new(&[USERINFO]);
+        let id_token = id_token("90342.ASDFJWFA");
+        let params = UserInfoParams { id_token: Some(&id_token), ..UserInfoParams::default() };
+        assert!(matches!(
+            client().userinfo_with(&http, &token(), &params).await,
+            Err(ClientError::Jwt(JwtError::InvalidClaim("sub")))
+        ));
+    }
+
+    #[tokio::test]
+    async fn userinfo_jwt() {
+        let key = PrivateKey::from_pem(test_keys::EC_P256_PKCS8).unwrap();
+        let keys = JwkSet { keys: vec![key.public_jwk()] };
+        let jwt = jwt::encode(
+            &json!({"iss": "https://example.com", "aud": "foo", "sub": "248289761001"}),
+            &key,
+        ).await.unwrap();
+        let http = MockHttp::with_responses(vec![HttpResponse {
+            status: 200,
+            headers: vec![(String::from("content-type"), String::from("application/jwt"))],
+            body: jwt.into_bytes(),
+        }]);
+        let id_token = id_token("248289761001");
+
+        let params = UserInfoParams { keys: Some(&keys), id_token: Some(&id_token) };
+        let claims = client().userinfo_with(&http, &token(), &params).await.unwrap();
+        assert_eq!("248289761001", claims.sub);
+
+        assert!(matches!(
+            client().userinfo(&http, &token()).await,
+            Err(ClientError::Jwt(JwtError::UnknownKey))
+        ));
+    }
+
+    #[tokio::test]
+    async fn userinfo_unsupported() {
+        let client = Client::new(Test::new(), String::from("foo"), String::from("bar"), None);
+        let http = MockHttp::new(&[USERINFO]);
+        assert!(matches!(
+            client.userinfo(&http, &token()).await,
+            Err(ClientError::MissingEndpoint(_))
+        ));
+    }
+}
@@ -36,11 +36,70 @@ pub enum OAuth2ErrorCode {
     /// resource owner.
     InvalidScope,
 
+    /// The device authorization request is still pending as the end user hasn't yet completed
+    /// the user-interaction steps.
+    ///
+    /// See [RFC 8628, section 3.5](https://tools.ietf.org/html/rfc8628#section-3.5).
+    AuthorizationPending,
+
+    /// A variant of `AuthorizationPending`; polling should continue, but the interval must be
+    /// increased by 5 seconds for this and all subsequent requests.
+    ///
+    /// See [RFC 8628, section 3.5](https://tools.ietf.org/html/rfc8628#section-3.5).
+    SlowDown,
+
+    /// The resource owner or authorization server denied the request.
+    AccessDenied,
+
+    /// The device code has expired, and the device authorization session has concluded.
+    ///
+    /// See [RFC 8628, section 3.5](https://tools.ietf.org/html/rfc8628#section-3.5).
+    ExpiredToken,
+
+    /// The authorization server does not support the revocation of the presented token type.
+    ///
+    /// See [RFC 7009, section 2.2.1](https://tools.ietf.org/html/rfc7009#section-2.2.1).
+    UnsupportedTokenType,
+
+    /// The authorization server does not support obtaining an authorization code using this
+    /// method.
+    ///
+    /// See [RFC 6749, section 4.1.2.1](http://tools.ietf.org/html/rfc6749#section-4.1.2.1).
+    UnsupportedResponseType,
+
+    /// The authorization server encountered an unexpected condition that prevented it from
+    /// fulfilling the request.
+    ServerError,
+
+    /// The authorization server is currently unable to handle the request due to a temporary
+    /// overloading or maintenance of the server.
+    TemporarilyUnavailable,
+
+    /// The value of one or more redirection URIs is invalid.
+    ///
+    /// See [RFC 7591, section 3.2.2](https://tools.ietf.org/html/rfc7591#section-3.2.2).
+    InvalidRedirectUri,
+
+    /// The value of one of the client metadata fields is invalid.
+    ///
+    /// See [RFC 7591, section 3.2.2](https://tools.ietf.org/html/rfc7591#section-3.2.2).
+    InvalidClientMetadata,
+
+    /// The software statement presented is invalid.
+    ///
+    /// See [RFC 7591, section 3.2.2](https://tools.ietf.org/html/rfc7591#section-3.2.2).
+    InvalidSoftwareStatement,
+
+    /// The software statement presented is not approved for use by this authorization server.
+    ///
+    /// See [RFC 7591, section 3.2.2](https://tools.ietf.org/html/rfc7591#section-3.2.2).
+    UnapprovedSoftwareStatement,
+
     /// An unrecognized error code, not defined in RFC 6749.
     Unrecognized(String),
 }
 
-impl<'a> From<&'a str> for OAuth2ErrorCode {
+impl From<&str> for OAuth2ErrorCode {
     fn from(s: &str) -> OAuth2ErrorCode {
         match s {
             "invalid_request" => OAuth2ErrorCode::InvalidRequest,
@@ -49,6 +108,18 @@ impl<'a> From<&'a str> for OAuth2ErrorCode {
             "unauthorized_client" => OAuth2ErrorCode::UnauthorizedClient,
             "unsupported_grant_type" => OAuth2ErrorCode::UnsupportedGrantType,
             "invalid_scope" => OAuth2ErrorCode::InvalidScope,
+            "authorization_pending" => OAuth2ErrorCode::AuthorizationPending,
+            "slow_down" => OAuth2ErrorCode::SlowDown,
+            "access_denied" => OAuth2ErrorCode::AccessDenied,
+            "expired_token" => OAuth2ErrorCode::ExpiredToken,
+            "unsupported_token_type" => OAuth2ErrorCode::UnsupportedTokenType,
+            "unsupported_response_type" => OAuth2ErrorCode::UnsupportedResponseType,
+            "server_error" => OAuth2ErrorCode::ServerError,
+            "temporarily_unavailable" => OAuth2ErrorCode::TemporarilyUnavailable,
+            "invalid_redirect_uri" => OAuth2ErrorCode::InvalidRedirectUri,
+            "invalid_client_metadata" => OAuth2ErrorCode::InvalidClientMetadata,
+            "invalid_software_statement" => OAuth2ErrorCode::InvalidSoftwareStatement,
+            "unapproved_software_statement" => OAuth2ErrorCode::UnapprovedSoftwareStatement,
             s => OAuth2ErrorCode::Unrecognized(s.to_owned()),
         }
     }
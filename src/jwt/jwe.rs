@@ -0,0 +1,165 @@
+use ring::aead::{self, Aad, LessSafeKey, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::agreement::{self, EphemeralPrivateKey, UnparsedPublicKey, ECDH_P256};
+use ring::digest;
+use ring::rand::SystemRandom;
+use serde_json::json;
+
+use crate::jwt::{Curve, Jwk, JwtError, VerificationKey};
+use crate::util::{base64url, random_bytes};
+
+/// The only supported content encryption algorithm.
+const ENC: &str = "A256GCM";
+
+/// Encrypts `plaintext` to a public key with the compact serialization.
+///
+/// The key must be a P-256 `EC` key not restricted to signatures. The content encryption key is
+/// agreed directly with `ECDH-ES` and the content is encrypted with `A256GCM`. `cty` is set to
+/// `content_type`, such as `JWT` for nested JWTs.
+///
+/// See [RFC 7516](https://tools.ietf.org/html/rfc7516) and
+/// [RFC 7518, section 4.6](https://tools.ietf.org/html/rfc7518#section-4.6).
+pub fn encrypt(
+    plaintext: &[u8],
+    content_type: Option<&str>,
+    key: &Jwk,
+) -> Result<String, JwtError> {
+    let point = encryption_point(key)?;
+
+    let rng = SystemRandom::new();
+    let ephemeral = EphemeralPrivateKey::generate(&ECDH_P256, &rng)
+        .map_err(|_| JwtError::Encryption)?;
+    let epk = ephemeral.compute_public_key().map_err(|_| JwtError::Encryption)?;
+    let epk = epk.as_ref();
+
+    let mut header = json!({
+        "alg": "ECDH-ES",
+        "enc": ENC,
+        "epk": {
+            "kty": "EC",
+            "crv": "P-256",
+            "x": base64url(&epk[1..33]),
+            "y": base64url(&epk[33..]),
+        },
+    });
+    if let Some(ref kid) = key.kid {
+        header["kid"] = kid.as_str().into();
+    }
+    if let Some(cty) = content_type {
+        header["cty"] = cty.into();
+    }
+    let header = base64url(header.to_string());
+
+    let cek = agreement::agree_ephemeral(
+        ephemeral,
+        &UnparsedPublicKey::new(&ECDH_P256, point),
+        concat_kdf,
+    ).map_err(|_| JwtError::InvalidKey)?;
+    let cek = UnboundKey::new(&AES_256_GCM, &cek).expect("key length is correct");
+
+    let iv = random_bytes(NONCE_LEN);
+    let mut ciphertext = plaintext.to_vec();
+    let tag = LessSafeKey::new(cek)
+        .seal_in_place_separate_tag(
+            aead::Nonce::try_assume_unique_for_key(&iv).unwrap(),
+            Aad::from(header.as_bytes()),
+            &mut ciphertext,
+        )
+        .map_err(|_| JwtError::Encryption)?;
+
+    // The encrypted key is empty with direct key agreement.
+    Ok(format!("{}..{}.{}.{}", header, base64url(iv), base64url(ciphertext), base64url(tag)))
+}
+
+/// Returns the uncompressed point of a P-256 key usable with `ECDH-ES`.
+pub(crate) fn encryption_point(key: &Jwk) -> Result<Vec<u8>, JwtError> {
+    if key.use_.as_ref().is_some_and(|u| u != "enc")
+        || key.alg.as_ref().is_some_and(|a| a != "ECDH-ES")
+    {
+        return Err(JwtError::InvalidKey);
+    }
+    match key.verification_key()? {
+        VerificationKey::Ec { curve: Curve::P256, point } => Ok(point),
+        _ => Err(JwtError::InvalidKey),
+    }
+}
+
+/// Derives a 256-bit content encryption key from the shared secret `z`, with empty party
+/// information.
+///
+/// See [RFC 7518, section 4.6.2](https://tools.ietf.org/html/rfc7518#section-4.6.2).
+fn concat_kdf(z: &[u8]) -> Vec<u8> {
+    let mut input = Vec::new();
+    input.extend_from_slice(&1u32.to_be_bytes());
+    input.extend_from_slice(z);
+    input.extend_from_slice(&(ENC.len() as u32).to_be_bytes());
+    input.extend_from_slice(ENC.as_bytes());
+    input.extend_from_slice(&0u32.to_be_bytes());
+    input.extend_from_slice(&0u32.to_be_bytes());
+    input.extend_from_slice(&256u32.to_be_bytes());
+    digest::digest(&digest::SHA256, &input).as_ref().to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    fn decode(part: &str) -> Vec<u8> {
+        base64::decode_config(part, base64::URL_SAFE_NO_PAD).unwrap()
+    }
+
+    #[test]
+    fn encrypt_decrypt() {
+        let rng = SystemRandom::new();
+        let recipient = EphemeralPrivateKey::generate(&ECDH_P256, &rng).unwrap();
+        let public = recipient.compute_public_key().unwrap();
+        let jwk = Jwk {
+            kty: String::from("EC"),
+            kid: Some(String::from("enc1")),
+            use_: Some(String::from("enc")),
+            crv: Some(String::from("P-256")),
+            x: Some(base64url(&public.as_ref()[1..33])),
+            y: Some(base64url(&public.as_ref()[33..])),
+            ..Jwk::default()
+        };
+
+        let jwe = encrypt(b"a.b.c", Some("JWT"), &jwk).unwrap();
+        let parts: Vec<&str> = jwe.split('.').collect();
+        assert_eq!(5, parts.len());
+        assert_eq!("", parts[1]);
+
+        let header: Value = serde_json::from_slice(&decode(parts[0])).unwrap();
+        assert_eq!("ECDH-ES", header["alg"]);
+        assert_eq!("A256GCM", header["enc"]);
+        assert_eq!("enc1", header["kid"]);
+        assert_eq!("JWT", header["cty"]);
+
+        let mut epk = vec![0x04];
+        epk.extend(decode(header["epk"]["x"].as_str().unwrap()));
+        epk.extend(decode(header["epk"]["y"].as_str().unwrap()));
+        let cek = agreement::agree_ephemeral(
+            recipient,
+            &UnparsedPublicKey::new(&ECDH_P256, epk),
+            concat_kdf,
+        ).unwrap();
+
+        let key = LessSafeKey::new(UnboundKey::new(&AES_256_GCM, &cek).unwrap());
+        let mut in_out = decode(parts[3]);
+        in_out.extend(decode(parts[4]));
+        let plaintext = key.open_in_place(
+            aead::Nonce::try_assume_unique_for_key(&decode(parts[2])).unwrap(),
+            Aad::from(parts[0].as_bytes()),
+            &mut in_out,
+        ).unwrap();
+        assert_eq!(b"a.b.c", plaintext);
+    }
+
+    #[test]
+    fn encrypt_invalid_key() {
+        let jwk = Jwk { kty: String::from("RSA"), ..Jwk::default() };
+        assert_eq!(JwtError::InvalidKey, encrypt(b"", None, &jwk).unwrap_err());
+
+        let jwk = Jwk { use_: Some(String::from("sig")), ..Jwk::default() };
+        assert_eq!(JwtError::InvalidKey, encrypt(b"", None, &jwk).unwrap_err());
+    }
+}
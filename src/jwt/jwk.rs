@@ -0,0 +1,275 @@
+use ring::signature::{
+    self, RsaPublicKeyComponents, UnparsedPublicKey, VerificationAlgorithm,
+};
+use serde_json::Value;
+
+use crate::client::response::{FromResponse, ParseError};
+use crate::jwt::{jwe, Algorithm, JwtError};
+
+/// A JSON Web Key.
+///
+/// Only the members of public keys are kept.
+///
+/// See [RFC 7517, section 4](https://tools.ietf.org/html/rfc7517#section-4).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Jwk {
+    /// Key type: `RSA`, `EC` or `OKP`.
+    pub kty: String,
+
+    /// Key ID.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kid: Option<String>,
+
+    /// Algorithm the key is intended for.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub alg: Option<String>,
+
+    /// Public key use: `sig` or `enc`.
+    #[serde(rename = "use", skip_serializing_if = "Option::is_none")]
+    pub use_: Option<String>,
+
+    /// Curve of `EC` and `OKP` keys.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub crv: Option<String>,
+
+    /// RSA modulus, base64url-encoded.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub n: Option<String>,
+
+    /// RSA public exponent, base64url-encoded.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub e: Option<String>,
+
+    /// EC x coordinate or OKP public key, base64url-encoded.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub x: Option<String>,
+
+    /// EC y coordinate, base64url-encoded.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub y: Option<String>,
+}
+
+impl Jwk {
+    /// Converts the key into a signature verification key.
+    pub fn verification_key(&self) -> Result<VerificationKey, JwtError> {
+        fn decode(member: &Option<String>) -> Result<Vec<u8>, JwtError> {
+            let member = member.as_ref().ok_or(JwtError::InvalidKey)?;
+            base64::decode_config(member, base64::URL_SAFE_NO_PAD)
+                .map_err(|_| JwtError::InvalidKey)
+        }
+
+        match (&self.kty[..], self.crv.as_ref().map(|s| &s[..])) {
+            ("RSA", _) => Ok(VerificationKey::Rsa { n: decode(&self.n)?, e: decode(&self.e)? }),
+            ("EC", Some(crv @ "P-256")) | ("EC", Some(crv @ "P-384")) => {
+                let curve = if crv == "P-256" { Curve::P256 } else { Curve::P384 };
+                let mut point = vec![0x04];
+                point.extend(decode(&self.x)?);
+                point.extend(decode(&self.y)?);
+                Ok(VerificationKey::Ec { curve, point })
+            }
+            ("OKP", Some("Ed25519")) => Ok(VerificationKey::Ed25519(decode(&self.x)?)),
+            _ => Err(JwtError::InvalidKey),
+        }
+    }
+
+    /// Returns true if the key may verify signatures with `alg`.
+    ///
+    /// Encryption keys, keys restricted to another algorithm and keys of the wrong type are
+    /// excluded.
+    pub fn accepts(&self, alg: Algorithm) -> bool {
+        if self.use_.as_ref().is_some_and(|u| u != "sig") {
+            return false;
+        }
+        if self.alg.as_ref().is_some_and(|a| a != alg.as_str()) {
+            return false;
+        }
+        self.verification_key().is_ok_and(|key| key.supports(alg))
+    }
+}
+
+/// A JSON Web Key Set.
+///
+/// See [RFC 7517, section 5](https://tools.ietf.org/html/rfc7517#section-5).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JwkSet {
+    /// The keys.
+    pub keys: Vec<Jwk>,
+}
+
+impl JwkSet {
+    /// Returns the keys that may verify a signature with `alg` and, if given, key ID `kid`.
+    pub fn find<'a>(
+        &'a self,
+        kid: Option<&'a str>,
+        alg: Algorithm,
+    ) -> impl Iterator<Item = &'a Jwk> + 'a {
+        self.keys.iter()
+            .filter(move |key| kid.is_none() || key.kid.as_ref().map(|s| &s[..]) == kid)
+            .filter(move |key| key.accepts(alg))
+    }
+
+    /// Returns the first key that [`jwt::encrypt`](crate::jwt::encrypt) can encrypt to.
+    pub fn encryption_key(&self) -> Option<&Jwk> {
+        self.keys.iter().find(|key| jwe::encryption_point(key).is_ok())
+    }
+}
+
+impl FromResponse for JwkSet {
+    fn from_response(json: &Value) -> Result<Self, ParseError> {
+        let keys = json.get("keys")
+            .and_then(Value::as_array)
+            .ok_or(ParseError::ExpectedFieldType("keys", "array"))?;
+
+        // Skip keys this crate cannot represent rather than rejecting the whole set.
+        let keys = keys.iter()
+            .filter_map(|key| serde_json::from_value(key.clone()).ok())
+            .collect();
+        Ok(JwkSet { keys })
+    }
+}
+
+/// Elliptic curves of EC keys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Curve {
+    /// NIST P-256.
+    P256,
+
+    /// NIST P-384.
+    P384,
+}
+
+/// A public key that verifies JWS signatures.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VerificationKey {
+    /// RSA public key.
+    Rsa {
+        /// Big-endian modulus.
+        n: Vec<u8>,
+
+        /// Big-endian public exponent.
+        e: Vec<u8>,
+    },
+
+    /// EC public key.
+    Ec {
+        /// The curve.
+        curve: Curve,
+
+        /// Uncompressed point.
+        point: Vec<u8>,
+    },
+
+    /// Ed25519 public key.
+    Ed25519(Vec<u8>),
+}
+
+impl VerificationKey {
+    /// Returns true if the key is of the type `alg` requires.
+    pub fn supports(&self, alg: Algorithm) -> bool {
+        self.rsa_params(alg).is_some() || self.algorithm(alg).is_some()
+    }
+
+    /// Verifies `signature` over `message` made with `alg`.
+    pub fn verify(&self, alg: Algorithm, message: &[u8], signature: &[u8]) -> Result<(), JwtError> {
+        if let Some(params) = self.rsa_params(alg) {
+            if let VerificationKey::Rsa { ref n, ref e } = *self {
+                return RsaPublicKeyComponents { n, e }
+                    .verify(params, message, signature)
+                    .map_err(|_| JwtError::InvalidSignature);
+            }
+        }
+        let algorithm = self.algorithm(alg).ok_or(JwtError::UnsupportedAlgorithm)?;
+        let key = match *self {
+            VerificationKey::Ec { ref point, .. } => point,
+            VerificationKey::Ed25519(ref key) => key,
+            VerificationKey::Rsa { .. } => unreachable!(),
+        };
+        UnparsedPublicKey::new(algorithm, key)
+            .verify(message, signature)
+            .map_err(|_| JwtError::InvalidSignature)
+    }
+
+    fn rsa_params(&self, alg: Algorithm) -> Option<&'static signature::RsaParameters> {
+        match *self {
+            VerificationKey::Rsa { .. } => match alg {
+                Algorithm::RS256 => Some(&signature::RSA_PKCS1_2048_8192_SHA256),
+                Algorithm::RS384 => Some(&signature::RSA_PKCS1_2048_8192_SHA384),
+                Algorithm::RS512 => Some(&signature::RSA_PKCS1_2048_8192_SHA512),
+                Algorithm::PS256 => Some(&signature::RSA_PSS_2048_8192_SHA256),
+                Algorithm::PS384 => Some(&signature::RSA_PSS_2048_8192_SHA384),
+                Algorithm::PS512 => Some(&signature::RSA_PSS_2048_8192_SHA512),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    fn algorithm(&self, alg: Algorithm) -> Option<&'static dyn VerificationAlgorithm> {
+        match (self, alg) {
+            (VerificationKey::Ec { curve: Curve::P256, .. }, Algorithm::ES256) =>
+                Some(&signature::ECDSA_P256_SHA256_FIXED),
+            (VerificationKey::Ec { curve: Curve::P384, .. }, Algorithm::ES384) =>
+                Some(&signature::ECDSA_P384_SHA384_FIXED),
+            (VerificationKey::Ed25519(_), Algorithm::EdDSA) => Some(&signature::ED25519),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::jwt::{test_keys, PrivateKey, Signer};
+
+    #[test]
+    fn from_response() {
+        let json = r#"
+            {
+                "keys": [
+                    {"kty":"EC","crv":"P-384","kid":"1","x":"AA","y":"AA","use":"sig"},
+                    {"kty":"RSA","kid":"2","n":"AQAB","e":"AQAB","alg":"RS256"},
+                    {"kty":"RSA","kid":"3","n":"AQAB","e":"AQAB","use":"enc"},
+                    {"kid":"4"}
+                ]
+            }
+        "#.parse().unwrap();
+        let keys = JwkSet::from_response(&json).unwrap();
+        assert_eq!(3, keys.keys.len());
+        assert_eq!(Some("P-384"), keys.keys[0].crv.as_ref().map(|s| &s[..]));
+
+        let found: Vec<_> = keys.find(None, Algorithm::RS256).map(|k| &k.kid).collect();
+        assert_eq!(vec![&Some(String::from("2"))], found);
+        assert_eq!(0, keys.find(None, Algorithm::PS256).count());
+        assert_eq!(1, keys.find(Some("1"), Algorithm::ES384).count());
+        assert_eq!(0, keys.find(Some("1"), Algorithm::ES256).count());
+    }
+
+    #[test]
+    fn encryption_key() {
+        let signing = PrivateKey::from_pem(test_keys::EC_P256_PKCS8).unwrap().public_jwk();
+        let encryption = Jwk {
+            kid: Some(String::from("enc")),
+            alg: None,
+            use_: Some(String::from("enc")),
+            ..signing.clone()
+        };
+        let keys = JwkSet { keys: vec![signing.clone(), encryption.clone()] };
+        assert_eq!(Some(&encryption), keys.encryption_key());
+        assert_eq!(None, JwkSet { keys: vec![signing] }.encryption_key());
+    }
+
+    #[tokio::test]
+    async fn verify() {
+        for pem in &[test_keys::RSA_PKCS8, test_keys::EC_P256_PKCS8, test_keys::ED25519_PKCS8] {
+            let key = PrivateKey::from_pem(pem).unwrap();
+            let signature = key.sign(b"message").await.unwrap();
+            let public = key.public_jwk().verification_key().unwrap();
+            assert_eq!(Ok(()), public.verify(key.algorithm(), b"message", &signature));
+            assert_eq!(
+                Err(JwtError::InvalidSignature),
+                public.verify(key.algorithm(), b"massage", &signature)
+            );
+        }
+    }
+}
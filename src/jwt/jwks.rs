@@ -0,0 +1,252 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serde_json::Value;
+use url::Url;
+
+use crate::client::http_client::{HttpRequest, Method, ACCEPT};
+use crate::client::response::FromResponse;
+use crate::client::{ClientError, HttpClient};
+use crate::jwt::{self, Algorithm, JwkSet};
+
+/// Time keys are cached for when the response has no `Cache-Control` max-age.
+const DEFAULT_MAX_AGE: Duration = Duration::from_secs(60 * 60);
+
+/// Minimum time between fetches triggered by unknown key IDs.
+const DEFAULT_REFETCH_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+#[derive(Debug, Default)]
+struct Cache {
+    keys: Arc<JwkSet>,
+    fetched: Option<Instant>,
+    expires: Option<Instant>,
+}
+
+/// Fetches and caches a JSON Web Key Set.
+///
+/// Keys are cached for the `Cache-Control` max-age of the response. A JWT signed with a key that
+/// is not in the cache, as happens after the provider rotates its keys, triggers a refetch, at
+/// most once per refetch interval.
+///
+/// ```no_run
+/// # #[cfg(feature="reqwest-client")] {
+/// use inth_oauth2_async::jwt::JwksClient;
+/// use inth_oauth2_async::oidc::IdTokenVerifier;
+/// use inth_oauth2_async::provider::{google, Provider};
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// # let id_token = "";
+/// let http = reqwest::Client::new();
+/// let jwks = JwksClient::new(google::Web.jwks_uri().unwrap().clone());
+///
+/// let keys = jwks.keys_for(&http, id_token).await.unwrap();
+/// let claims = IdTokenVerifier::new("https://accounts.google.com", "CLIENT_ID", &keys)
+///     .verify(id_token)
+///     .unwrap();
+/// # } }
+/// ```
+#[derive(Debug)]
+pub struct JwksClient {
+    uri: Url,
+    refetch_interval: Duration,
+    cache: Mutex<Cache>,
+}
+
+impl JwksClient {
+    /// Creates a client for the JWK Set at `uri`, typically the provider's
+    /// [`jwks_uri`](crate::provider::Provider::jwks_uri).
+    pub fn new(uri: Url) -> Self {
+        JwksClient {
+            uri,
+            refetch_interval: DEFAULT_REFETCH_INTERVAL,
+            cache: Mutex::new(Cache::default()),
+        }
+    }
+
+    /// Sets the minimum time between fetches triggered by unknown key IDs. Defaults to five
+    /// minutes.
+    pub fn with_refetch_interval(mut self, interval: Duration) -> Self {
+        self.refetch_interval = interval;
+        self
+    }
+
+    /// Returns the JWK Set URI.
+    pub fn uri(&self) -> &Url { &self.uri }
+
+    /// Returns the cached keys, fetching them if the cache has expired.
+    pub async fn keys(&self, http_client: &impl HttpClient) -> Result<Arc<JwkSet>, ClientError> {
+        {
+            let cache = self.cache.lock().unwrap();
+            if cache.expires.is_some_and(|expires| Instant::now() < expires) {
+                return Ok(Arc::clone(&cache.keys));
+            }
+        }
+        self.fetch(http_client).await
+    }
+
+    /// Returns the keys to verify `jwt` with.
+    ///
+    /// If no cached key matches the `kid` and `alg` header parameters, the keys are refetched,
+    /// unless they were fetched within the refetch interval.
+    pub async fn keys_for(
+        &self,
+        http_client: &impl HttpClient,
+        jwt: &str,
+    ) -> Result<Arc<JwkSet>, ClientError> {
+        let header = jwt::decode_header(jwt)?;
+        let alg: Algorithm = header["alg"].as_str()
+            .ok_or(jwt::JwtError::UnsupportedAlgorithm)?
+            .parse()?;
+        let kid = header["kid"].as_str();
+
+        let keys = self.keys(http_client).await?;
+        if keys.find(kid, alg).next().is_some() {
+            return Ok(keys);
+        }
+
+        let recently_fetched = self.cache.lock().unwrap().fetched
+            .is_some_and(|fetched| fetched.elapsed() < self.refetch_interval);
+        if recently_fetched {
+            return Ok(keys);
+        }
+        self.fetch(http_client).await
+    }
+
+    /// Verifies the signature of `jwt` and returns its claims. See [`jwt::decode`].
+    pub async fn decode(
+        &self,
+        http_client: &impl HttpClient,
+        jwt: &str,
+    ) -> Result<Value, ClientError> {
+        let keys = self.keys_for(http_client, jwt).await?;
+        Ok(jwt::decode(jwt, &keys)?)
+    }
+
+    async fn fetch(&self, http_client: &impl HttpClient) -> Result<Arc<JwkSet>, ClientError> {
+        let request = HttpRequest::new(Method::Get, self.uri.as_str())
+            .header(ACCEPT, "application/json");
+        let response = http_client.send(request).await?;
+        if !response.is_success() {
+            return Err(ClientError::HttpStatus(response.status));
+        }
+        let keys = Arc::new(JwkSet::from_response(&response.json()?)?);
+
+        let now = Instant::now();
+        let max_age = max_age(response.header("Cache-Control")).unwrap_or(DEFAULT_MAX_AGE);
+        let mut cache = self.cache.lock().unwrap();
+        *cache = Cache {
+            keys: Arc::clone(&keys),
+            fetched: Some(now),
+            expires: Some(now + max_age),
+        };
+        Ok(keys)
+    }
+}
+
+/// Parses the freshness lifetime from a `Cache-Control` header value. `no-cache` and `no-store`
+/// are a lifetime of zero.
+///
+/// See [RFC 9111, section 5.2.2](https://tools.ietf.org/html/rfc9111#section-5.2.2).
+fn max_age(cache_control: Option<&str>) -> Option<Duration> {
+    let mut max_age = None;
+    for directive in cache_control?.split(',') {
+        let directive = directive.trim().to_ascii_lowercase();
+        if directive == "no-cache" || directive == "no-store" {
+            return Some(Duration::from_secs(0));
+        }
+        if let Some(seconds) = directive.strip_prefix("max-age=") {
+            max_age = seconds.trim_matches('"').parse().ok().map(Duration::from_secs);
+        }
+    }
+    max_age
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    use crate::client::http_client::HttpResponse;
+    use crate::client::test_util::MockHttp;
+    use crate::jwt::{test_keys, PrivateKey};
+
+    fn response(keys: &[&PrivateKey], cache_control: &str) -> HttpResponse {
+        let keys = JwkSet { keys: keys.iter().map(|key| key.public_jwk()).collect() };
+        HttpResponse {
+            status: 200,
+            headers: vec![(String::from("cache-control"), String::from(cache_control))],
+            body: serde_json::to_vec(&keys).unwrap(),
+        }
+    }
+
+    fn client() -> JwksClient {
+        JwksClient::new(Url::parse("http://example.com/jwks").unwrap())
+    }
+
+    #[test]
+    fn parse_max_age() {
+        assert_eq!(None, max_age(None));
+        assert_eq!(None, max_age(Some("public")));
+        assert_eq!(Some(Duration::from_secs(600)), max_age(Some("public, max-age=600")));
+        assert_eq!(Some(Duration::from_secs(0)), max_age(Some("max-age=600, no-cache")));
+    }
+
+    #[tokio::test]
+    async fn keys_cached() {
+        let key = PrivateKey::from_pem(test_keys::RSA_PKCS8).unwrap().with_key_id("k1");
+        let http = MockHttp::with_responses(vec![response(&[&key], "public, max-age=600")]);
+        let jwks = client();
+
+        let keys = jwks.keys(&http).await.unwrap();
+        assert_eq!(vec![key.public_jwk()], keys.keys);
+        jwks.keys(&http).await.unwrap();
+        assert_eq!(vec!["http://example.com/jwks"], http.urls());
+    }
+
+    #[tokio::test]
+    async fn keys_expired() {
+        let key = PrivateKey::from_pem(test_keys::RSA_PKCS8).unwrap();
+        let http = MockHttp::with_responses(vec![response(&[&key], "no-store")]);
+        let jwks = client();
+
+        jwks.keys(&http).await.unwrap();
+        jwks.keys(&http).await.unwrap();
+        assert_eq!(2, http.urls().len());
+    }
+
+    #[tokio::test]
+    async fn decode_rotated() {
+        let old = PrivateKey::from_pem(test_keys::RSA_PKCS8).unwrap().with_key_id("k1");
+        let new = PrivateKey::from_pem(test_keys::EC_P256_PKCS8).unwrap().with_key_id("k2");
+        let http = MockHttp::with_responses(vec![
+            response(&[&old], "max-age=600"),
+            response(&[&old, &new], "max-age=600"),
+        ]);
+        let jwks = client().with_refetch_interval(Duration::from_secs(0));
+
+        let jwt = jwt::encode(&json!({"iss": "foo"}), &old).await.unwrap();
+        assert_eq!(json!({"iss": "foo"}), jwks.decode(&http, &jwt).await.unwrap());
+        assert_eq!(1, http.urls().len());
+
+        let jwt = jwt::encode(&json!({"iss": "bar"}), &new).await.unwrap();
+        assert_eq!(json!({"iss": "bar"}), jwks.decode(&http, &jwt).await.unwrap());
+        assert_eq!(2, http.urls().len());
+    }
+
+    #[tokio::test]
+    async fn decode_unknown_key_rate_limited() {
+        let old = PrivateKey::from_pem(test_keys::RSA_PKCS8).unwrap().with_key_id("k1");
+        let new = PrivateKey::from_pem(test_keys::EC_P256_PKCS8).unwrap().with_key_id("k2");
+        let http = MockHttp::with_responses(vec![response(&[&old], "max-age=600")]);
+        let jwks = client();
+
+        jwks.keys(&http).await.unwrap();
+        let jwt = jwt::encode(&json!({"iss": "bar"}), &new).await.unwrap();
+        assert!(matches!(
+            jwks.decode(&http, &jwt).await,
+            Err(ClientError::Jwt(jwt::JwtError::UnknownKey))
+        ));
+        assert_eq!(1, http.urls().len());
+    }
+}
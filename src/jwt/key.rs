@@ -0,0 +1,292 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use ring::rand::SystemRandom;
+use ring::rsa::PublicKeyComponents;
+use ring::signature::{
+    EcdsaKeyPair, Ed25519KeyPair, KeyPair as _, RsaEncoding, RsaKeyPair,
+    ECDSA_P256_SHA256_FIXED_SIGNING, RSA_PKCS1_SHA256, RSA_PSS_SHA256,
+};
+
+use crate::jwt::{Algorithm, Jwk, JwtError, Signer};
+use crate::util::base64url;
+
+enum KeyPair {
+    Rsa(RsaKeyPair),
+    Ecdsa(EcdsaKeyPair),
+    Ed25519(Ed25519KeyPair),
+}
+
+/// An in-memory RSA, P-256 EC or Ed25519 private key.
+///
+/// RSA keys sign with `RS256`, or `PS256` if set with [`PrivateKey::with_algorithm`], EC keys
+/// with `ES256` and Ed25519 keys with `EdDSA`.
+pub struct PrivateKey {
+    pair: KeyPair,
+    algorithm: Algorithm,
+    key_id: Option<String>,
+    rng: SystemRandom,
+}
+
+impl PrivateKey {
+    /// Parses a PEM-encoded PKCS#8 (`PRIVATE KEY`) or PKCS#1 (`RSA PRIVATE KEY`) key.
+    pub fn from_pem(pem: &str) -> Result<Self, JwtError> {
+        let (label, der) = decode_pem(pem).ok_or(JwtError::InvalidKey)?;
+        match label {
+            "PRIVATE KEY" => PrivateKey::from_pkcs8_der(&der),
+            "RSA PRIVATE KEY" => {
+                let pair = RsaKeyPair::from_der(&der).map_err(|_| JwtError::InvalidKey)?;
+                Ok(PrivateKey::new(KeyPair::Rsa(pair)))
+            }
+            _ => Err(JwtError::InvalidKey),
+        }
+    }
+
+    /// Reads a PEM-encoded key from a file, as with [`PrivateKey::from_pem`].
+    ///
+    /// Keys that cannot be parsed are `InvalidData` errors.
+    pub fn from_pem_file(path: impl AsRef<Path>) -> io::Result<Self> {
+        let pem = fs::read_to_string(path)?;
+        PrivateKey::from_pem(&pem).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+
+    /// Parses a DER-encoded PKCS#8 key.
+    pub fn from_pkcs8_der(der: &[u8]) -> Result<Self, JwtError> {
+        if let Ok(pair) = RsaKeyPair::from_pkcs8(der) {
+            return Ok(PrivateKey::new(KeyPair::Rsa(pair)));
+        }
+        let rng = SystemRandom::new();
+        if let Ok(pair) = EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, der, &rng) {
+            return Ok(PrivateKey::new(KeyPair::Ecdsa(pair)));
+        }
+        Ed25519KeyPair::from_pkcs8_maybe_unchecked(der)
+            .map(|pair| PrivateKey::new(KeyPair::Ed25519(pair)))
+            .map_err(|_| JwtError::InvalidKey)
+    }
+
+    /// Sets the key ID sent in the `kid` header parameter.
+    pub fn with_key_id(mut self, key_id: impl Into<String>) -> Self {
+        self.key_id = Some(key_id.into());
+        self
+    }
+
+    /// Sets the signature algorithm. Only RSA keys support another algorithm, `PS256`.
+    pub fn with_algorithm(mut self, algorithm: Algorithm) -> Result<Self, JwtError> {
+        match (&self.pair, algorithm) {
+            (KeyPair::Rsa(_), Algorithm::RS256)
+            | (KeyPair::Rsa(_), Algorithm::PS256)
+            | (KeyPair::Ecdsa(_), Algorithm::ES256)
+            | (KeyPair::Ed25519(_), Algorithm::EdDSA) => {
+                self.algorithm = algorithm;
+                Ok(self)
+            }
+            _ => Err(JwtError::UnsupportedAlgorithm),
+        }
+    }
+
+    /// Returns the DER-encoded public key: an `RSAPublicKey` for RSA keys, an uncompressed
+    /// point for EC keys, or the raw key for Ed25519 keys.
+    pub fn public_key_der(&self) -> &[u8] {
+        match self.pair {
+            KeyPair::Rsa(ref pair) => pair.public_key().as_ref(),
+            KeyPair::Ecdsa(ref pair) => pair.public_key().as_ref(),
+            KeyPair::Ed25519(ref pair) => pair.public_key().as_ref(),
+        }
+    }
+
+    /// Returns the public key as a JWK, for publishing in a JWK Set.
+    pub fn public_jwk(&self) -> Jwk {
+        let mut jwk = Jwk {
+            kid: self.key_id.clone(),
+            alg: Some(self.algorithm().as_str().into()),
+            use_: Some(String::from("sig")),
+            ..Jwk::default()
+        };
+        match self.pair {
+            KeyPair::Rsa(ref pair) => {
+                let components = PublicKeyComponents::<Vec<u8>>::from(pair.public());
+                jwk.kty = String::from("RSA");
+                jwk.n = Some(base64url(components.n));
+                jwk.e = Some(base64url(components.e));
+            }
+            KeyPair::Ecdsa(ref pair) => {
+                // Uncompressed point: 0x04 || x || y.
+                let point = pair.public_key().as_ref();
+                jwk.kty = String::from("EC");
+                jwk.crv = Some(String::from("P-256"));
+                jwk.x = Some(base64url(&point[1..33]));
+                jwk.y = Some(base64url(&point[33..]));
+            }
+            KeyPair::Ed25519(ref pair) => {
+                jwk.kty = String::from("OKP");
+                jwk.crv = Some(String::from("Ed25519"));
+                jwk.x = Some(base64url(pair.public_key()));
+            }
+        }
+        jwk
+    }
+
+    fn new(pair: KeyPair) -> Self {
+        let algorithm = match pair {
+            KeyPair::Rsa(_) => Algorithm::RS256,
+            KeyPair::Ecdsa(_) => Algorithm::ES256,
+            KeyPair::Ed25519(_) => Algorithm::EdDSA,
+        };
+        PrivateKey { pair, algorithm, key_id: None, rng: SystemRandom::new() }
+    }
+}
+
+impl fmt::Debug for PrivateKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        f.debug_struct("PrivateKey")
+            .field("algorithm", &self.algorithm())
+            .field("key_id", &self.key_id)
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait::async_trait]
+impl Signer for PrivateKey {
+    fn algorithm(&self) -> Algorithm { self.algorithm }
+
+    fn key_id(&self) -> Option<&str> {
+        self.key_id.as_ref().map(|s| &s[..])
+    }
+
+    async fn sign(&self, message: &[u8]) -> Result<Vec<u8>, JwtError> {
+        match self.pair {
+            KeyPair::Rsa(ref pair) => {
+                let padding: &dyn RsaEncoding = if self.algorithm == Algorithm::PS256 {
+                    &RSA_PSS_SHA256
+                } else {
+                    &RSA_PKCS1_SHA256
+                };
+                let mut signature = vec![0; pair.public().modulus_len()];
+                pair.sign(padding, &self.rng, message, &mut signature)
+                    .map_err(|_| JwtError::Signing)?;
+                Ok(signature)
+            }
+            KeyPair::Ecdsa(ref pair) => pair.sign(&self.rng, message)
+                .map(|signature| signature.as_ref().to_vec())
+                .map_err(|_| JwtError::Signing),
+            KeyPair::Ed25519(ref pair) => Ok(pair.sign(message).as_ref().to_vec()),
+        }
+    }
+
+    fn jwk(&self) -> Option<Jwk> { Some(self.public_jwk()) }
+}
+
+/// Decodes the first PEM block, returning its label and contents.
+fn decode_pem(pem: &str) -> Option<(&str, Vec<u8>)> {
+    let start = pem.find("-----BEGIN ")?;
+    let rest = &pem[start + "-----BEGIN ".len()..];
+    let label_end = rest.find("-----")?;
+    let label = &rest[..label_end];
+    let body = &rest[label_end + "-----".len()..];
+    let end = body.find(&format!("-----END {}-----", label))?;
+    let base64: String = body[..end].chars().filter(|c| !c.is_whitespace()).collect();
+    let der = base64::decode(base64).ok()?;
+    Some((label, der))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ring::signature::{
+        UnparsedPublicKey, ECDSA_P256_SHA256_FIXED, ED25519, RSA_PKCS1_2048_8192_SHA256,
+        RSA_PSS_2048_8192_SHA256,
+    };
+
+    use crate::jwt::test_keys;
+
+    #[tokio::test]
+    async fn rsa_sign() {
+        let key = PrivateKey::from_pem(test_keys::RSA_PKCS8).unwrap();
+        assert_eq!(Algorithm::RS256, key.algorithm());
+        let signature = key.sign(b"message").await.unwrap();
+        UnparsedPublicKey::new(&RSA_PKCS1_2048_8192_SHA256, key.public_key_der())
+            .verify(b"message", &signature)
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn rsa_pss_sign() {
+        let key = PrivateKey::from_pem(test_keys::RSA_PKCS8)
+            .unwrap()
+            .with_algorithm(Algorithm::PS256)
+            .unwrap();
+        assert_eq!(Algorithm::PS256, key.algorithm());
+        let signature = key.sign(b"message").await.unwrap();
+        UnparsedPublicKey::new(&RSA_PSS_2048_8192_SHA256, key.public_key_der())
+            .verify(b"message", &signature)
+            .unwrap();
+
+        let key = PrivateKey::from_pem(test_keys::EC_P256_PKCS8).unwrap();
+        assert_eq!(
+            JwtError::UnsupportedAlgorithm,
+            key.with_algorithm(Algorithm::PS256).unwrap_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn ec_sign() {
+        let key = PrivateKey::from_pem(test_keys::EC_P256_PKCS8).unwrap().with_key_id("k1");
+        assert_eq!(Algorithm::ES256, key.algorithm());
+        assert_eq!(Some("k1"), key.key_id());
+        let signature = key.sign(b"message").await.unwrap();
+        UnparsedPublicKey::new(&ECDSA_P256_SHA256_FIXED, key.public_key_der())
+            .verify(b"message", &signature)
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn ed25519_sign() {
+        let key = PrivateKey::from_pem(test_keys::ED25519_PKCS8).unwrap();
+        assert_eq!(Algorithm::EdDSA, key.algorithm());
+        let signature = key.sign(b"message").await.unwrap();
+        UnparsedPublicKey::new(&ED25519, key.public_key_der())
+            .verify(b"message", &signature)
+            .unwrap();
+    }
+
+    #[test]
+    fn public_jwk() {
+        let key = PrivateKey::from_pem(test_keys::EC_P256_PKCS8).unwrap().with_key_id("k1");
+        let jwk = key.public_jwk();
+        assert_eq!("EC", jwk.kty);
+        assert_eq!(Some("k1"), jwk.kid.as_ref().map(|s| &s[..]));
+        assert_eq!(Some("ES256"), jwk.alg.as_ref().map(|s| &s[..]));
+
+        let key = PrivateKey::from_pem(test_keys::RSA_PKCS8).unwrap();
+        assert_eq!(Some("AQAB"), key.public_jwk().e.as_ref().map(|s| &s[..]));
+    }
+
+    #[test]
+    fn from_pem_file() {
+        let path = std::env::temp_dir().join(format!("inth-oauth2-{}.pem", std::process::id()));
+        fs::write(&path, test_keys::ED25519_PKCS8).unwrap();
+        let key = PrivateKey::from_pem_file(&path);
+        fs::write(&path, "foo").unwrap();
+        let invalid = PrivateKey::from_pem_file(&path);
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(Algorithm::EdDSA, key.unwrap().algorithm());
+        assert_eq!(io::ErrorKind::InvalidData, invalid.unwrap_err().kind());
+        assert_eq!(
+            io::ErrorKind::NotFound,
+            PrivateKey::from_pem_file(&path).unwrap_err().kind()
+        );
+    }
+
+    #[test]
+    fn invalid_pem() {
+        assert_eq!(JwtError::InvalidKey, PrivateKey::from_pem("foo").unwrap_err());
+        assert_eq!(
+            JwtError::InvalidKey,
+            PrivateKey::from_pem("-----BEGIN CERTIFICATE-----\nAAAA\n-----END CERTIFICATE-----")
+                .unwrap_err()
+        );
+    }
+}
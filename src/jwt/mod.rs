@@ -0,0 +1,359 @@
+//! JSON Web Tokens.
+//!
+//! Just enough of [RFC 7519](https://tools.ietf.org/html/rfc7519),
+//! [RFC 7515](https://tools.ietf.org/html/rfc7515) and
+//! [RFC 7516](https://tools.ietf.org/html/rfc7516) to sign and encrypt the JWTs that OAuth 2.0
+//! extensions send to authorization servers, and to verify the JWTs they return.
+
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use ring::hmac;
+use serde_json::{json, Value};
+
+use crate::util::base64url;
+
+mod key;
+pub use self::key::PrivateKey;
+
+mod rotation;
+pub use self::rotation::SigningKeys;
+
+mod jwk;
+pub use self::jwk::{Curve, Jwk, JwkSet, VerificationKey};
+
+mod jwks;
+pub use self::jwks::JwksClient;
+
+mod jwe;
+pub use self::jwe::encrypt;
+
+#[cfg(test)]
+pub(crate) mod test_keys;
+
+/// JWS signature algorithms.
+///
+/// See [RFC 7518, section 3.1](https://tools.ietf.org/html/rfc7518#section-3.1).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Algorithm {
+    /// RSASSA-PKCS1-v1_5 using SHA-256.
+    RS256,
+
+    /// RSASSA-PKCS1-v1_5 using SHA-384.
+    RS384,
+
+    /// RSASSA-PKCS1-v1_5 using SHA-512.
+    RS512,
+
+    /// RSASSA-PSS using SHA-256.
+    PS256,
+
+    /// RSASSA-PSS using SHA-384.
+    PS384,
+
+    /// RSASSA-PSS using SHA-512.
+    PS512,
+
+    /// ECDSA using P-256 and SHA-256.
+    ES256,
+
+    /// ECDSA using P-384 and SHA-384.
+    ES384,
+
+    /// EdDSA using Ed25519. See [RFC 8037](https://tools.ietf.org/html/rfc8037).
+    EdDSA,
+}
+
+impl Algorithm {
+    /// Returns the value of the `alg` header parameter.
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            Algorithm::RS256 => "RS256",
+            Algorithm::RS384 => "RS384",
+            Algorithm::RS512 => "RS512",
+            Algorithm::PS256 => "PS256",
+            Algorithm::PS384 => "PS384",
+            Algorithm::PS512 => "PS512",
+            Algorithm::ES256 => "ES256",
+            Algorithm::ES384 => "ES384",
+            Algorithm::EdDSA => "EdDSA",
+        }
+    }
+}
+
+impl FromStr for Algorithm {
+    type Err = JwtError;
+
+    /// Parses an `alg` header parameter. `none` and MAC algorithms are unsupported.
+    fn from_str(s: &str) -> Result<Self, JwtError> {
+        match s {
+            "RS256" => Ok(Algorithm::RS256),
+            "RS384" => Ok(Algorithm::RS384),
+            "RS512" => Ok(Algorithm::RS512),
+            "PS256" => Ok(Algorithm::PS256),
+            "PS384" => Ok(Algorithm::PS384),
+            "PS512" => Ok(Algorithm::PS512),
+            "ES256" => Ok(Algorithm::ES256),
+            "ES384" => Ok(Algorithm::ES384),
+            "EdDSA" => Ok(Algorithm::EdDSA),
+            _ => Err(JwtError::UnsupportedAlgorithm),
+        }
+    }
+}
+
+/// Produces JWS signatures.
+///
+/// Implemented by [`PrivateKey`]; implement it to keep keys elsewhere, such as in a KMS or HSM
+/// reached over the network.
+#[async_trait::async_trait]
+pub trait Signer: Send + Sync {
+    /// The algorithm of the signatures produced.
+    fn algorithm(&self) -> Algorithm;
+
+    /// The key ID to send in the `kid` header parameter, if any.
+    fn key_id(&self) -> Option<&str> { None }
+
+    /// Signs the JWS signing input.
+    async fn sign(&self, message: &[u8]) -> Result<Vec<u8>, JwtError>;
+
+    /// The public key as a JWK, for publishing in the client's JWK Set, if known.
+    fn jwk(&self) -> Option<Jwk> { None }
+}
+
+/// JWT errors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JwtError {
+    /// The key could not be parsed or is of an unsupported type.
+    InvalidKey,
+
+    /// The signer failed to produce a signature.
+    Signing,
+
+    /// Encryption failed.
+    Encryption,
+
+    /// The JWT is not a well-formed JWS compact serialization.
+    Malformed,
+
+    /// The `alg` header parameter is missing, `none`, or unsupported.
+    UnsupportedAlgorithm,
+
+    /// No key matches the `kid` and `alg` header parameters.
+    UnknownKey,
+
+    /// The signature is invalid.
+    InvalidSignature,
+
+    /// The named claim is missing or has an unacceptable value.
+    InvalidClaim(&'static str),
+}
+
+impl fmt::Display for JwtError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match *self {
+            JwtError::InvalidKey => write!(f, "Invalid or unsupported key"),
+            JwtError::Signing => write!(f, "Signing failed"),
+            JwtError::Encryption => write!(f, "Encryption failed"),
+            JwtError::Malformed => write!(f, "Malformed JWT"),
+            JwtError::UnsupportedAlgorithm => write!(f, "Unsupported JWS algorithm"),
+            JwtError::UnknownKey => write!(f, "No matching key"),
+            JwtError::InvalidSignature => write!(f, "Invalid signature"),
+            JwtError::InvalidClaim(claim) => write!(f, "Invalid {} claim", claim),
+        }
+    }
+}
+
+impl Error for JwtError {
+    fn description(&self) -> &str { "JWT error" }
+}
+
+/// Encodes and signs a JWT with the compact serialization.
+///
+/// The header contains `alg`, `typ` and, if the signer has one, `kid`.
+pub async fn encode<S: Signer + ?Sized>(claims: &Value, signer: &S) -> Result<String, JwtError> {
+    encode_with_type(claims, signer, "JWT").await
+}
+
+/// Like [`encode`], with another `typ` header parameter.
+pub(crate) async fn encode_with_type<S: Signer + ?Sized>(
+    claims: &Value,
+    signer: &S,
+    typ: &str,
+) -> Result<String, JwtError> {
+    let mut header = json!({
+        "alg": signer.algorithm().as_str(),
+        "typ": typ,
+    });
+    if let Some(kid) = signer.key_id() {
+        header["kid"] = Value::from(kid);
+    }
+    let jwt = signing_input(&header, claims);
+    let signature = signer.sign(jwt.as_bytes()).await?;
+    Ok(with_signature(jwt, &signature))
+}
+
+/// Encodes and signs a JWT with `HS256`, using a shared secret such as a client secret.
+///
+/// MAC algorithms are not otherwise supported, since their keys cannot be published.
+pub(crate) fn encode_hs256(claims: &Value, secret: &[u8]) -> String {
+    let key = hmac::Key::new(hmac::HMAC_SHA256, secret);
+    let header = json!({ "alg": "HS256", "typ": "JWT" });
+    let jwt = signing_input(&header, claims);
+    let signature = hmac::sign(&key, jwt.as_bytes());
+    with_signature(jwt, signature.as_ref())
+}
+
+/// Returns the JWS signing input, the encoded header and claims.
+fn signing_input(header: &Value, claims: &Value) -> String {
+    let mut jwt = base64url(header.to_string());
+    jwt.push('.');
+    jwt.push_str(&base64url(claims.to_string()));
+    jwt
+}
+
+fn with_signature(mut jwt: String, signature: &[u8]) -> String {
+    jwt.push('.');
+    jwt.push_str(&base64url(signature));
+    jwt
+}
+
+/// Verifies the signature of a JWT with a key from `keys`, and returns its claims.
+///
+/// The key is selected by the `kid` header parameter, if present, and must be compatible with
+/// the `alg` header parameter. No claims are validated.
+pub fn decode(jwt: &str, keys: &JwkSet) -> Result<Value, JwtError> {
+    decode_with_algorithm(jwt, keys).map(|(_, claims)| claims)
+}
+
+/// Like [`decode`], also returning the signature algorithm.
+pub(crate) fn decode_with_algorithm(
+    jwt: &str,
+    keys: &JwkSet,
+) -> Result<(Algorithm, Value), JwtError> {
+    let mut parts = jwt.rsplitn(2, '.');
+    let signature = parts.next().ok_or(JwtError::Malformed)?;
+    let signing_input = parts.next().ok_or(JwtError::Malformed)?;
+    let (header, claims) = match signing_input.split('.').collect::<Vec<_>>()[..] {
+        [header, claims] => (decode_part(header)?, decode_part(claims)?),
+        _ => return Err(JwtError::Malformed),
+    };
+    let signature = base64::decode_config(signature, base64::URL_SAFE_NO_PAD)
+        .map_err(|_| JwtError::Malformed)?;
+
+    let alg: Algorithm = header["alg"].as_str()
+        .ok_or(JwtError::UnsupportedAlgorithm)?
+        .parse()?;
+    let kid = header["kid"].as_str();
+
+    let mut result = Err(JwtError::UnknownKey);
+    for key in keys.find(kid, alg) {
+        // A key this crate cannot use must not prevent another from verifying the signature.
+        let key = match key.verification_key() {
+            Ok(key) => key,
+            Err(_) => continue,
+        };
+        result = key.verify(alg, signing_input.as_bytes(), &signature);
+        if result.is_ok() {
+            break;
+        }
+    }
+    result.map(|()| (alg, claims))
+}
+
+/// Decodes the header of a JWT without verifying it.
+pub fn decode_header(jwt: &str) -> Result<Value, JwtError> {
+    decode_part(jwt.split('.').next().unwrap_or(""))
+}
+
+/// Decodes the claims of a JWT without verifying its signature.
+pub(crate) fn decode_claims_unverified(jwt: &str) -> Result<Value, JwtError> {
+    match jwt.split('.').collect::<Vec<_>>()[..] {
+        [_, claims, _] => decode_part(claims),
+        _ => Err(JwtError::Malformed),
+    }
+}
+
+/// Decodes a base64url-encoded JSON object.
+fn decode_part(part: &str) -> Result<Value, JwtError> {
+    let bytes = base64::decode_config(part, base64::URL_SAFE_NO_PAD)
+        .map_err(|_| JwtError::Malformed)?;
+    let value: Value = serde_json::from_slice(&bytes).map_err(|_| JwtError::Malformed)?;
+    if value.is_object() { Ok(value) } else { Err(JwtError::Malformed) }
+}
+
+/// Returns the current time as a JWT NumericDate.
+pub(crate) fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Fixed;
+    #[async_trait::async_trait]
+    impl Signer for Fixed {
+        fn algorithm(&self) -> Algorithm { Algorithm::RS256 }
+        fn key_id(&self) -> Option<&str> { Some("k1") }
+        async fn sign(&self, _message: &[u8]) -> Result<Vec<u8>, JwtError> { Ok(vec![0xff; 4]) }
+    }
+
+    #[tokio::test]
+    async fn encode_compact() {
+        let jwt = encode(&json!({"iss": "foo"}), &Fixed).await.unwrap();
+        let parts: Vec<&str> = jwt.split('.').collect();
+        assert_eq!(3, parts.len());
+
+        let header: Value =
+            serde_json::from_slice(&base64::decode_config(parts[0], base64::URL_SAFE_NO_PAD).unwrap())
+                .unwrap();
+        assert_eq!(json!({"alg": "RS256", "typ": "JWT", "kid": "k1"}), header);
+        assert_eq!("eyJpc3MiOiJmb28ifQ", parts[1]);
+        assert_eq!("_____w", parts[2]);
+    }
+
+    #[tokio::test]
+    async fn decode_verified() {
+        let key = PrivateKey::from_pem(test_keys::EC_P256_PKCS8).unwrap().with_key_id("k1");
+        let keys = JwkSet { keys: vec![key.public_jwk()] };
+        let jwt = encode(&json!({"iss": "foo"}), &key).await.unwrap();
+        assert_eq!(json!({"iss": "foo"}), decode(&jwt, &keys).unwrap());
+
+        let other = PrivateKey::from_pem(test_keys::EC_P256_PKCS8).unwrap().with_key_id("k2");
+        let jwt = encode(&json!({"iss": "foo"}), &other).await.unwrap();
+        assert_eq!(Err(JwtError::UnknownKey), decode(&jwt, &keys));
+    }
+
+    #[tokio::test]
+    async fn decode_skips_unusable_keys() {
+        let key = PrivateKey::from_pem(test_keys::EC_P256_PKCS8).unwrap();
+        let unusable = Jwk { x: Some(String::from("!")), ..key.public_jwk() };
+        let keys = JwkSet { keys: vec![unusable.clone(), key.public_jwk()] };
+        let jwt = encode(&json!({"iss": "foo"}), &key).await.unwrap();
+        assert_eq!(json!({"iss": "foo"}), decode(&jwt, &keys).unwrap());
+
+        let keys = JwkSet { keys: vec![unusable] };
+        assert_eq!(Err(JwtError::UnknownKey), decode(&jwt, &keys));
+    }
+
+    #[tokio::test]
+    async fn decode_invalid() {
+        let key = PrivateKey::from_pem(test_keys::RSA_PKCS8).unwrap();
+        let keys = JwkSet { keys: vec![key.public_jwk()] };
+        let jwt = encode(&json!({"iss": "foo"}), &key).await.unwrap();
+
+        let (signing_input, _) = jwt.rsplit_once('.').unwrap();
+        let forged = format!("{}.{}", signing_input, base64url([0; 256]));
+        assert_eq!(Err(JwtError::InvalidSignature), decode(&forged, &keys));
+
+        let unsigned = format!("{}.{}.", base64url(r#"{"alg":"none"}"#), base64url("{}"));
+        assert_eq!(Err(JwtError::UnsupportedAlgorithm), decode(&unsigned, &keys));
+
+        assert_eq!(Err(JwtError::Malformed), decode("foo.bar", &keys));
+    }
+}
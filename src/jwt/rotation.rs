@@ -0,0 +1,143 @@
+use std::fmt;
+use std::sync::Arc;
+
+use crate::jwt::{Algorithm, Jwk, JwkSet, JwtError, Signer};
+
+/// Several signing keys identified by `kid`, of which the current one signs.
+///
+/// Keys are rotated without downtime by publishing the new key alongside the current one,
+/// switching to it with [`SigningKeys::with_current`] once the authorization server has fetched
+/// the new JWK Set, and finally dropping the old key.
+///
+/// ```no_run
+/// use inth_oauth2_async::jwt::{PrivateKey, SigningKeys};
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let keys = SigningKeys::new(PrivateKey::from_pem_file("2023.pem")?.with_key_id("2023"))
+///     .with_key(PrivateKey::from_pem_file("2024.pem")?.with_key_id("2024"));
+/// let published = keys.jwks();
+/// // Later, once the authorization server knows both keys:
+/// let keys = keys.with_current("2024")?;
+/// // And once it no longer accepts assertions signed with the old one:
+/// let keys = keys.without_key("2023")?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct SigningKeys {
+    keys: Vec<Arc<dyn Signer>>,
+    current: usize,
+}
+
+impl SigningKeys {
+    /// Creates a set of the current key only.
+    pub fn new(current: impl Signer + 'static) -> Self {
+        SigningKeys { keys: vec![Arc::new(current)], current: 0 }
+    }
+
+    /// Adds a key which is published but does not sign.
+    pub fn with_key(mut self, key: impl Signer + 'static) -> Self {
+        self.keys.push(Arc::new(key));
+        self
+    }
+
+    /// Makes the key with ID `key_id` the current one.
+    pub fn with_current(mut self, key_id: &str) -> Result<Self, JwtError> {
+        self.current = self.keys.iter()
+            .position(|key| key.key_id() == Some(key_id))
+            .ok_or(JwtError::UnknownKey)?;
+        Ok(self)
+    }
+
+    /// Removes the key with ID `key_id`, unless it is the current one.
+    pub fn without_key(mut self, key_id: &str) -> Result<Self, JwtError> {
+        let index = self.keys.iter()
+            .position(|key| key.key_id() == Some(key_id))
+            .ok_or(JwtError::UnknownKey)?;
+        if index == self.current {
+            return Err(JwtError::InvalidKey);
+        }
+        self.keys.remove(index);
+        if index < self.current {
+            self.current -= 1;
+        }
+        Ok(self)
+    }
+
+    /// Returns the current key.
+    pub fn current(&self) -> &dyn Signer {
+        &*self.keys[self.current]
+    }
+
+    /// Returns the public keys to publish, for the `jwks` client metadata or a JWK Set document.
+    ///
+    /// Keys whose [`Signer::jwk`] is `None` are omitted.
+    pub fn jwks(&self) -> JwkSet {
+        JwkSet { keys: self.keys.iter().filter_map(|key| key.jwk()).collect() }
+    }
+}
+
+impl fmt::Debug for SigningKeys {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        let key_ids: Vec<_> = self.keys.iter().map(|key| key.key_id()).collect();
+        f.debug_struct("SigningKeys")
+            .field("key_ids", &key_ids)
+            .field("current", &self.current().key_id())
+            .finish()
+    }
+}
+
+#[async_trait::async_trait]
+impl Signer for SigningKeys {
+    fn algorithm(&self) -> Algorithm { self.current().algorithm() }
+
+    fn key_id(&self) -> Option<&str> { self.current().key_id() }
+
+    async fn sign(&self, message: &[u8]) -> Result<Vec<u8>, JwtError> {
+        self.current().sign(message).await
+    }
+
+    fn jwk(&self) -> Option<Jwk> { self.current().jwk() }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::jwt::{self, test_keys, PrivateKey};
+
+    fn keys() -> SigningKeys {
+        SigningKeys::new(PrivateKey::from_pem(test_keys::EC_P256_PKCS8).unwrap().with_key_id("k1"))
+            .with_key(PrivateKey::from_pem(test_keys::ED25519_PKCS8).unwrap().with_key_id("k2"))
+    }
+
+    #[tokio::test]
+    async fn rotate() {
+        let keys = keys();
+        assert_eq!(Some("k1"), keys.key_id());
+        assert_eq!(Algorithm::ES256, keys.algorithm());
+
+        let jwks = keys.jwks();
+        let token = jwt::encode(&json!({ "sub": "foo" }), &keys).await.unwrap();
+        assert_eq!("k1", jwt::decode_header(&token).unwrap()["kid"]);
+        assert_eq!(json!({ "sub": "foo" }), jwt::decode(&token, &jwks).unwrap());
+
+        let keys = keys.with_current("k2").unwrap();
+        assert_eq!(Some("k2"), keys.key_id());
+        assert_eq!(Algorithm::EdDSA, keys.algorithm());
+        let token = jwt::encode(&json!({ "sub": "foo" }), &keys).await.unwrap();
+        assert_eq!(json!({ "sub": "foo" }), jwt::decode(&token, &jwks).unwrap());
+
+        let keys = keys.without_key("k1").unwrap();
+        let kids: Vec<_> = keys.jwks().keys.into_iter().map(|jwk| jwk.kid).collect();
+        assert_eq!(vec![Some(String::from("k2"))], kids);
+        assert_eq!(Some("k2"), keys.key_id());
+    }
+
+    #[test]
+    fn unknown_key() {
+        assert_eq!(JwtError::UnknownKey, keys().with_current("k3").unwrap_err());
+        assert_eq!(JwtError::UnknownKey, keys().without_key("k3").unwrap_err());
+        assert_eq!(JwtError::InvalidKey, keys().without_key("k1").unwrap_err());
+    }
+}
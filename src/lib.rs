@@ -19,7 +19,8 @@
 //! - GitHub
 //! - Imgur
 //!
-//! Support for other providers can be added by implementing the `Provider` trait.
+//! Support for other providers can be added by implementing the `Provider` trait, or by
+//! discovering their authorization server metadata with `provider::DiscoveredProvider`.
 //!
 //! ## Token types
 //!
@@ -42,6 +43,19 @@
 //! );
 //! ```
 //!
+//! ### Authenticating with a private key
+//!
+//! ```no_run
+//! # use inth_oauth2_async::Client;
+//! # use inth_oauth2_async::provider::google::Installed;
+//! # let client = Client::new(Installed, String::new(), String::new(), None);
+//! use inth_oauth2_async::client::ClientAuth;
+//! use inth_oauth2_async::jwt::PrivateKey;
+//!
+//! let key = PrivateKey::from_pem_file("client.pem").unwrap().with_key_id("2024");
+//! let client = client.with_auth(ClientAuth::private_key_jwt(key));
+//! ```
+//!
 //! ### Constructing an authorization URI
 //!
 //! ```
@@ -52,6 +66,54 @@
 //! println!("Authorize the application by clicking on the link: {}", auth_uri);
 //! ```
 //!
+//! ### Using PKCE
+//!
+//! ```
+//! # use inth_oauth2_async::Client;
+//! # use inth_oauth2_async::provider::google::Installed;
+//! # let client = Client::new(Installed, String::new(), String::new(), None);
+//! use inth_oauth2_async::client::{AuthParams, TokenParams};
+//! use inth_oauth2_async::pkce::Pkce;
+//!
+//! // Keep the verifier until the code is exchanged, e.g. with
+//! // `client.request_token_with(&http, code, &TokenParams { pkce: Some(&pkce), ..TokenParams::default() })`.
+//! let pkce = Pkce::s256();
+//! let auth_uri = client.auth_uri_with(&AuthParams {
+//!     scope: Some("scope"),
+//!     state: Some("state"),
+//!     pkce: Some(&pkce),
+//!     ..AuthParams::default()
+//! });
+//! # let _ = TokenParams { pkce: Some(&pkce), ..TokenParams::default() };
+//! ```
+//!
+//! ### Using an OpenID Connect nonce
+//!
+//! ```
+//! # use inth_oauth2_async::Client;
+//! # use inth_oauth2_async::provider::google::Web;
+//! # let client = Client::new(Web, String::from("client_id"), String::new(), None);
+//! # let keys = inth_oauth2_async::jwt::JwkSet::default();
+//! # let id_token = "";
+//! use inth_oauth2_async::client::AuthParams;
+//! use inth_oauth2_async::oidc::{IdTokenVerifier, Nonce};
+//!
+//! // Keep the nonce until the ID token is received.
+//! let nonce = Nonce::new();
+//! let auth_uri = client.auth_uri_with(&AuthParams {
+//!     scope: Some("openid email"),
+//!     state: Some("state"),
+//!     nonce: Some(&nonce),
+//!     ..AuthParams::default()
+//! });
+//!
+//! let verifier = IdTokenVerifier {
+//!     nonce: Some(nonce.as_str()),
+//!     ..IdTokenVerifier::new("https://accounts.google.com", &client.client_id, &keys)
+//! };
+//! # let _ = verifier.verify(id_token);
+//! ```
+//!
 //! ### Requesting an access token
 //!
 //! ```no_run
@@ -161,6 +223,13 @@ pub mod token;
 pub mod provider;
 pub mod error;
 pub mod client;
+pub mod pkce;
+pub mod jwt;
+pub mod oidc;
+pub mod state;
+pub mod rar;
+
+mod util;
 
 pub use token::{Token, Lifetime};
 pub use client::{Client, ClientError};
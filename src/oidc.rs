@@ -0,0 +1,442 @@
+//! OpenID Connect.
+//!
+//! Validation of ID tokens, as returned by [`Token::id_token`](crate::token::Token::id_token).
+//!
+//! See [OpenID Connect Core 1.0](https://openid.net/specs/openid-connect-core-1_0.html).
+
+use std::time::Duration;
+
+use ring::digest;
+use serde::{Deserialize, Deserializer};
+use serde_json::{Map, Value};
+
+use crate::jwt::{self, Algorithm, JwkSet, JwtError};
+use crate::util::{base64url, constant_time_eq, random_token};
+
+/// Default allowed clock skew between the client and the provider.
+pub const DEFAULT_LEEWAY: Duration = Duration::from_secs(60);
+
+/// A nonce binding an ID token to the authorization request, preventing replay.
+///
+/// A new nonce must be generated for every authorization request and kept, like the state, until
+/// the ID token is received.
+///
+/// See [OpenID Connect Core 1.0, section 15.5.2](https://openid.net/specs/openid-connect-core-1_0.html#NonceNotes).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Nonce(String);
+
+impl Nonce {
+    /// Generates a nonce of 32 random bytes, encoded as base64url.
+    pub fn new() -> Self {
+        Nonce(random_token(32))
+    }
+
+    /// Returns the nonce, sent as the `nonce` parameter of the authorization request.
+    pub fn as_str(&self) -> &str { &self.0 }
+
+    /// Checks the `nonce` claim of an ID token.
+    pub fn verify(&self, claims: &IdTokenClaims) -> Result<(), JwtError> {
+        match claims.nonce {
+            Some(ref nonce) if constant_time_eq(nonce.as_bytes(), self.0.as_bytes()) => Ok(()),
+            _ => Err(JwtError::InvalidClaim("nonce")),
+        }
+    }
+
+    /// Checks the `nonce` claim of an ID token received from the token endpoint, whose signature
+    /// need not be verified as it was received directly from the provider over TLS.
+    ///
+    /// See [OpenID Connect Core 1.0, section 3.1.3.7](https://openid.net/specs/openid-connect-core-1_0.html#IDTokenValidation).
+    pub(crate) fn verify_id_token(&self, id_token: &str) -> Result<(), JwtError> {
+        let claims = jwt::decode_claims_unverified(id_token)?;
+        match claims["nonce"].as_str() {
+            Some(nonce) if constant_time_eq(nonce.as_bytes(), self.0.as_bytes()) => Ok(()),
+            _ => Err(JwtError::InvalidClaim("nonce")),
+        }
+    }
+}
+
+impl Default for Nonce {
+    fn default() -> Self { Nonce::new() }
+}
+
+impl From<String> for Nonce {
+    /// Restores a previously generated nonce.
+    fn from(nonce: String) -> Self { Nonce(nonce) }
+}
+
+/// Standard claims about the end-user.
+///
+/// See [OpenID Connect Core 1.0, section 5.1](https://openid.net/specs/openid-connect-core-1_0.html#StandardClaims).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct StandardClaims {
+    /// Subject identifier.
+    pub sub: String,
+
+    /// Full name.
+    pub name: Option<String>,
+
+    /// Given name(s) or first name(s).
+    pub given_name: Option<String>,
+
+    /// Surname(s) or last name(s).
+    pub family_name: Option<String>,
+
+    /// Middle name(s).
+    pub middle_name: Option<String>,
+
+    /// Casual name.
+    pub nickname: Option<String>,
+
+    /// Shorthand name by which the end-user wishes to be referred to.
+    pub preferred_username: Option<String>,
+
+    /// Profile page URL.
+    pub profile: Option<String>,
+
+    /// Profile picture URL.
+    pub picture: Option<String>,
+
+    /// Web page or blog URL.
+    pub website: Option<String>,
+
+    /// Preferred e-mail address.
+    pub email: Option<String>,
+
+    /// Whether the e-mail address has been verified.
+    pub email_verified: Option<bool>,
+
+    /// Gender.
+    pub gender: Option<String>,
+
+    /// Birthday, as `YYYY-MM-DD` or `YYYY`.
+    pub birthdate: Option<String>,
+
+    /// Time zone, such as `Europe/Paris`.
+    pub zoneinfo: Option<String>,
+
+    /// Locale, as a BCP47 language tag.
+    pub locale: Option<String>,
+
+    /// Preferred telephone number.
+    pub phone_number: Option<String>,
+
+    /// Whether the phone number has been verified.
+    pub phone_number_verified: Option<bool>,
+
+    /// Preferred postal address.
+    pub address: Option<Address>,
+
+    /// Time the information was last updated, in seconds since the epoch.
+    pub updated_at: Option<i64>,
+
+    /// Other claims.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Postal address claim.
+///
+/// See [OpenID Connect Core 1.0, section 5.1.1](https://openid.net/specs/openid-connect-core-1_0.html#AddressClaim).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Address {
+    /// Full mailing address, formatted for display.
+    pub formatted: Option<String>,
+
+    /// Street address, possibly multi-line.
+    pub street_address: Option<String>,
+
+    /// City or locality.
+    pub locality: Option<String>,
+
+    /// State, province, prefecture or region.
+    pub region: Option<String>,
+
+    /// Zip or postal code.
+    pub postal_code: Option<String>,
+
+    /// Country name.
+    pub country: Option<String>,
+}
+
+/// Claims of a validated ID token.
+///
+/// See [OpenID Connect Core 1.0, section 2](https://openid.net/specs/openid-connect-core-1_0.html#IDToken).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IdTokenClaims {
+    /// Issuer identifier.
+    pub iss: String,
+
+    /// Audiences.
+    #[serde(deserialize_with = "one_or_many")]
+    pub aud: Vec<String>,
+
+    /// Expiry time, in seconds since the epoch.
+    pub exp: i64,
+
+    /// Issue time, in seconds since the epoch.
+    pub iat: i64,
+
+    /// Time of end-user authentication, in seconds since the epoch.
+    pub auth_time: Option<i64>,
+
+    /// Nonce sent in the authorization request.
+    pub nonce: Option<String>,
+
+    /// Authentication context class reference.
+    pub acr: Option<String>,
+
+    /// Authentication method references.
+    #[serde(default)]
+    pub amr: Vec<String>,
+
+    /// Authorized party.
+    pub azp: Option<String>,
+
+    /// Access token hash.
+    pub at_hash: Option<String>,
+
+    /// Authorization code hash.
+    pub c_hash: Option<String>,
+
+    /// Claims about the end-user, and any other claims.
+    #[serde(flatten)]
+    pub standard: StandardClaims,
+}
+
+/// Deserializes a string or an array of strings.
+fn one_or_many<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(String),
+        Many(Vec<String>),
+    }
+
+    Ok(match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(s) => vec![s],
+        OneOrMany::Many(v) => v,
+    })
+}
+
+/// ID token validation.
+///
+/// See [OpenID Connect Core 1.0, section 3.1.3.7](https://openid.net/specs/openid-connect-core-1_0.html#IDTokenValidation).
+#[derive(Debug, Clone, Copy)]
+pub struct IdTokenVerifier<'a> {
+    /// Expected `iss` claim, the provider's issuer identifier.
+    pub issuer: &'a str,
+
+    /// Client ID, which must be among the `aud` claim.
+    pub client_id: &'a str,
+
+    /// The provider's signing keys.
+    pub keys: &'a JwkSet,
+
+    /// Allowed clock skew when checking `exp` and `iat`.
+    pub leeway: Duration,
+
+    /// Nonce sent in the authorization request, which the `nonce` claim must equal.
+    pub nonce: Option<&'a str>,
+
+    /// Access token issued with the ID token, checked against the `at_hash` claim if present.
+    pub access_token: Option<&'a str>,
+
+    /// Authorization code issued with the ID token, checked against the `c_hash` claim if
+    /// present.
+    pub code: Option<&'a str>,
+}
+
+impl<'a> IdTokenVerifier<'a> {
+    /// Creates a verifier with the default leeway, checking no nonce or hashes.
+    pub fn new(issuer: &'a str, client_id: &'a str, keys: &'a JwkSet) -> Self {
+        IdTokenVerifier {
+            issuer,
+            client_id,
+            keys,
+            leeway: DEFAULT_LEEWAY,
+            nonce: None,
+            access_token: None,
+            code: None,
+        }
+    }
+
+    /// Verifies the signature and claims of an ID token, and returns its claims.
+    pub fn verify(&self, id_token: &str) -> Result<IdTokenClaims, JwtError> {
+        let (alg, claims) = jwt::decode_with_algorithm(id_token, self.keys)?;
+        let claims: IdTokenClaims =
+            serde_json::from_value(claims).map_err(|_| JwtError::Malformed)?;
+
+        if claims.iss != self.issuer {
+            return Err(JwtError::InvalidClaim("iss"));
+        }
+        if !claims.aud.iter().any(|aud| aud == self.client_id) {
+            return Err(JwtError::InvalidClaim("aud"));
+        }
+        match claims.azp {
+            Some(ref azp) if azp != self.client_id => return Err(JwtError::InvalidClaim("azp")),
+            None if claims.aud.len() > 1 => return Err(JwtError::InvalidClaim("azp")),
+            _ => {}
+        }
+
+        let now = jwt::now() as i64;
+        let leeway = self.leeway.as_secs() as i64;
+        if claims.exp + leeway <= now {
+            return Err(JwtError::InvalidClaim("exp"));
+        }
+        if claims.iat - leeway > now {
+            return Err(JwtError::InvalidClaim("iat"));
+        }
+
+        if let Some(nonce) = self.nonce {
+            Nonce::from(String::from(nonce)).verify(&claims)?;
+        }
+        if let (Some(at_hash), Some(access_token)) = (&claims.at_hash, self.access_token) {
+            if *at_hash != token_hash(alg, access_token) {
+                return Err(JwtError::InvalidClaim("at_hash"));
+            }
+        }
+        if let (Some(c_hash), Some(code)) = (&claims.c_hash, self.code) {
+            if *c_hash != token_hash(alg, code) {
+                return Err(JwtError::InvalidClaim("c_hash"));
+            }
+        }
+
+        Ok(claims)
+    }
+}
+
+/// Computes an `at_hash` or `c_hash` value: the base64url-encoded left half of the hash of the
+/// token, using the hash function of the ID token's signature algorithm.
+fn token_hash(alg: Algorithm, token: &str) -> String {
+    let algorithm = match alg {
+        Algorithm::RS256 | Algorithm::PS256 | Algorithm::ES256 => &digest::SHA256,
+        Algorithm::RS384 | Algorithm::PS384 | Algorithm::ES384 => &digest::SHA384,
+        // Ed25519 uses SHA-512.
+        Algorithm::RS512 | Algorithm::PS512 | Algorithm::EdDSA => &digest::SHA512,
+    };
+    let hash = digest::digest(algorithm, token.as_bytes());
+    let hash = hash.as_ref();
+    base64url(&hash[..hash.len() / 2])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    use crate::jwt::{test_keys, PrivateKey};
+
+    const ISSUER: &str = "https://accounts.example.com";
+
+    async fn sign(pem: &str, claims: Value) -> (String, JwkSet) {
+        let key = PrivateKey::from_pem(pem).unwrap().with_key_id("k1");
+        let keys = JwkSet { keys: vec![key.public_jwk()] };
+        (jwt::encode(&claims, &key).await.unwrap(), keys)
+    }
+
+    fn claims() -> Value {
+        let now = jwt::now();
+        json!({
+            "iss": ISSUER,
+            "aud": "foo",
+            "sub": "1234",
+            "exp": now + 3600,
+            "iat": now,
+            "nonce": "n-0S6_WzA2Mj",
+            "email": "alice@example.com",
+            "email_verified": true,
+            "hd": "example.com",
+        })
+    }
+
+    #[tokio::test]
+    async fn verify() {
+        for pem in &[test_keys::RSA_PKCS8, test_keys::EC_P256_PKCS8, test_keys::ED25519_PKCS8] {
+            let (id_token, keys) = sign(pem, claims()).await;
+            let claims = IdTokenVerifier {
+                nonce: Some("n-0S6_WzA2Mj"),
+                ..IdTokenVerifier::new(ISSUER, "foo", &keys)
+            }.verify(&id_token).unwrap();
+            assert_eq!(vec!["foo"], claims.aud);
+            assert_eq!("1234", claims.standard.sub);
+            assert_eq!(Some("alice@example.com"), claims.standard.email.as_ref().map(|s| &s[..]));
+            assert_eq!(Some(true), claims.standard.email_verified);
+            assert_eq!(Some(&json!("example.com")), claims.standard.extra.get("hd"));
+        }
+    }
+
+    #[tokio::test]
+    async fn verify_claims() {
+        async fn check(changes: Value, nonce: Option<&str>) -> Result<(), JwtError> {
+            let mut claims = claims();
+            for (k, v) in changes.as_object().unwrap() {
+                claims[k] = v.clone();
+            }
+            let (id_token, keys) = sign(test_keys::EC_P256_PKCS8, claims).await;
+            IdTokenVerifier { nonce, ..IdTokenVerifier::new(ISSUER, "foo", &keys) }
+                .verify(&id_token)
+                .map(|_| ())
+        }
+        let now = jwt::now();
+
+        assert_eq!(Ok(()), check(json!({}), None).await);
+        assert_eq!(Err(JwtError::InvalidClaim("iss")), check(json!({"iss": "x"}), None).await);
+        assert_eq!(Err(JwtError::InvalidClaim("aud")), check(json!({"aud": "bar"}), None).await);
+        assert_eq!(
+            Err(JwtError::InvalidClaim("azp")),
+            check(json!({"aud": ["foo", "bar"]}), None).await
+        );
+        assert_eq!(Ok(()), check(json!({"aud": ["foo", "bar"], "azp": "foo"}), None).await);
+        assert_eq!(Err(JwtError::InvalidClaim("azp")), check(json!({"azp": "bar"}), None).await);
+        assert_eq!(Err(JwtError::InvalidClaim("exp")), check(json!({"exp": now - 61}), None).await);
+        assert_eq!(Ok(()), check(json!({"exp": now - 30}), None).await);
+        assert_eq!(
+            Err(JwtError::InvalidClaim("iat")),
+            check(json!({"iat": now + 120}), None).await
+        );
+        assert_eq!(Err(JwtError::InvalidClaim("nonce")), check(json!({}), Some("other")).await);
+        assert_eq!(Err(JwtError::Malformed), check(json!({"sub": null}), None).await);
+    }
+
+    #[test]
+    fn nonce() {
+        let nonce = Nonce::new();
+        assert_eq!(43, nonce.as_str().len());
+        assert_ne!(nonce, Nonce::new());
+
+        let mut claims: IdTokenClaims = serde_json::from_value(claims()).unwrap();
+        assert_eq!(Err(JwtError::InvalidClaim("nonce")), nonce.verify(&claims));
+        claims.nonce = Some(nonce.as_str().into());
+        assert_eq!(Ok(()), nonce.verify(&claims));
+        claims.nonce = None;
+        assert_eq!(Err(JwtError::InvalidClaim("nonce")), nonce.verify(&claims));
+    }
+
+    #[tokio::test]
+    async fn verify_hashes() {
+        // Value from OpenID Connect Core 1.0, appendix A.4.
+        let code = "Qcb0Orv1zh30vL1MPRsbm-diHiMwcLyZvn1arpZv-Jxf_11jnpEX3Tgfvk";
+        assert_eq!("LDktKdoQak3Pk0cnXxCltA", token_hash(Algorithm::RS256, code));
+
+        let mut claims = claims();
+        claims["at_hash"] = token_hash(Algorithm::ES256, "aaaaaaaa").into();
+        claims["c_hash"] = token_hash(Algorithm::ES256, "cccccccc").into();
+        let (id_token, keys) = sign(test_keys::EC_P256_PKCS8, claims).await;
+        let verifier = IdTokenVerifier {
+            access_token: Some("aaaaaaaa"),
+            code: Some("cccccccc"),
+            ..IdTokenVerifier::new(ISSUER, "foo", &keys)
+        };
+        assert!(verifier.verify(&id_token).is_ok());
+        assert_eq!(
+            Err(JwtError::InvalidClaim("at_hash")),
+            IdTokenVerifier { access_token: Some("bbbbbbbb"), ..verifier }
+                .verify(&id_token)
+                .map(|_| ())
+        );
+        assert_eq!(
+            Err(JwtError::InvalidClaim("c_hash")),
+            IdTokenVerifier { code: Some("bbbbbbbb"), ..verifier }.verify(&id_token).map(|_| ())
+        );
+    }
+}
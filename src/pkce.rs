@@ -0,0 +1,105 @@
+//! Proof Key for Code Exchange.
+//!
+//! See [RFC 7636](https://tools.ietf.org/html/rfc7636).
+
+use ring::digest::{digest, SHA256};
+
+use crate::util::{base64url, random_token};
+
+/// Code challenge transformation methods.
+///
+/// See [RFC 7636, section 4.2](https://tools.ietf.org/html/rfc7636#section-4.2).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ChallengeMethod {
+    /// `code_challenge = BASE64URL(SHA256(code_verifier))`.
+    S256,
+
+    /// `code_challenge = code_verifier`. Only for servers which do not support `S256`.
+    Plain,
+}
+
+impl ChallengeMethod {
+    /// Returns the value of the `code_challenge_method` parameter.
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            ChallengeMethod::S256 => "S256",
+            ChallengeMethod::Plain => "plain",
+        }
+    }
+}
+
+/// A PKCE code verifier and its challenge method.
+///
+/// A new verifier must be generated for every authorization request and kept until the
+/// authorization code is exchanged for a token.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Pkce {
+    verifier: String,
+    method: ChallengeMethod,
+}
+
+impl Pkce {
+    /// Generates a random code verifier using the given challenge method.
+    ///
+    /// The verifier is 32 random bytes encoded as 43 characters of base64url, as recommended by
+    /// [RFC 7636, section 4.1](https://tools.ietf.org/html/rfc7636#section-4.1).
+    pub fn new(method: ChallengeMethod) -> Self {
+        Pkce {
+            verifier: random_token(32),
+            method,
+        }
+    }
+
+    /// Generates a random code verifier using the `S256` challenge method.
+    pub fn s256() -> Self {
+        Pkce::new(ChallengeMethod::S256)
+    }
+
+    /// Generates a random code verifier using the `plain` challenge method.
+    pub fn plain() -> Self {
+        Pkce::new(ChallengeMethod::Plain)
+    }
+
+    /// Returns the code verifier, sent as `code_verifier` in the token request.
+    pub fn verifier(&self) -> &str { &self.verifier }
+
+    /// Returns the challenge method, sent as `code_challenge_method` in the authorization request.
+    pub fn method(&self) -> ChallengeMethod { self.method }
+
+    /// Returns the code challenge, sent as `code_challenge` in the authorization request.
+    pub fn challenge(&self) -> String {
+        match self.method {
+            ChallengeMethod::S256 => base64url(digest(&SHA256, self.verifier.as_bytes())),
+            ChallengeMethod::Plain => self.verifier.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn s256_challenge() {
+        // RFC 7636, appendix B.
+        let pkce = Pkce {
+            verifier: String::from("dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk"),
+            method: ChallengeMethod::S256,
+        };
+        assert_eq!("E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM", pkce.challenge());
+    }
+
+    #[test]
+    fn plain_challenge() {
+        let pkce = Pkce::plain();
+        assert_eq!(pkce.verifier(), pkce.challenge());
+        assert_eq!("plain", pkce.method().as_str());
+    }
+
+    #[test]
+    fn verifier_length() {
+        let pkce = Pkce::s256();
+        assert_eq!(43, pkce.verifier().len());
+        assert_ne!(pkce.verifier(), Pkce::s256().verifier());
+    }
+}
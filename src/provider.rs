@@ -1,9 +1,15 @@
 //! Providers.
 
+use serde_json::json;
 use url::Url;
 
+use crate::client::ClientAuth;
+use crate::client::http_client::{HttpRequest, Method, ACCEPT, USER_AGENT};
 use crate::token::{Token, Lifetime, Bearer, Static, Refresh};
 
+pub mod discovery;
+pub use self::discovery::{DiscoveredProvider, Metadata};
+
 /// OAuth 2.0 providers.
 pub trait Provider {
     /// The lifetime of tokens issued by the provider.
@@ -12,6 +18,17 @@ pub trait Provider {
     /// The type of token issued by the provider.
     type Token: Token<Self::Lifetime>;
 
+    /// The issuer identifier of the authorization server, if known.
+    ///
+    /// See [RFC 8414, section 2](https://tools.ietf.org/html/rfc8414#section-2).
+    fn issuer(&self) -> Option<&str> { None }
+
+    /// Whether the authorization server includes the `iss` parameter in authorization responses,
+    /// in which case responses without it are rejected.
+    ///
+    /// See [RFC 9207, section 3](https://tools.ietf.org/html/rfc9207#section-3).
+    fn authorization_response_iss_parameter_supported(&self) -> bool { false }
+
     /// The authorization endpoint URI.
     ///
     /// See [RFC 6749, section 3.1](http://tools.ietf.org/html/rfc6749#section-3.1).
@@ -22,13 +39,50 @@ pub trait Provider {
     /// See [RFC 6749, section 3.2](http://tools.ietf.org/html/rfc6749#section-3.2).
     fn token_uri(&self) -> &Url;
 
-    /// Provider requires credentials via request body.
+    /// The pushed authorization request endpoint URI, if the provider supports pushed
+    /// authorization requests.
+    ///
+    /// See [RFC 9126, section 2](https://tools.ietf.org/html/rfc9126#section-2).
+    fn pushed_authorization_request_uri(&self) -> Option<&Url> { None }
+
+    /// The dynamic client registration endpoint URI, if the provider supports it.
+    ///
+    /// See [RFC 7591, section 3](https://tools.ietf.org/html/rfc7591#section-3).
+    fn registration_uri(&self) -> Option<&Url> { None }
+
+    /// The device authorization endpoint URI, if the provider supports the device authorization
+    /// grant.
+    ///
+    /// See [RFC 8628, section 3.1](https://tools.ietf.org/html/rfc8628#section-3.1).
+    fn device_authorization_uri(&self) -> Option<&Url> { None }
+
+    /// The token revocation endpoint URI, if the provider supports token revocation.
+    ///
+    /// See [RFC 7009, section 2](https://tools.ietf.org/html/rfc7009#section-2).
+    fn revocation_uri(&self) -> Option<&Url> { None }
+
+    /// The token introspection endpoint URI, if the provider supports token introspection.
+    ///
+    /// See [RFC 7662, section 2](https://tools.ietf.org/html/rfc7662#section-2).
+    fn introspection_uri(&self) -> Option<&Url> { None }
+
+    /// The JSON Web Key Set document URI, if the provider signs ID tokens or other JWTs.
+    ///
+    /// See [RFC 7517, section 5](https://tools.ietf.org/html/rfc7517#section-5).
+    fn jwks_uri(&self) -> Option<&Url> { None }
+
+    /// The OpenID Connect UserInfo endpoint URI.
+    ///
+    /// See [OpenID Connect Core 1.0, section 5.3](https://openid.net/specs/openid-connect-core-1_0.html#UserInfo).
+    fn userinfo_uri(&self) -> Option<&Url> { None }
+
+    /// The default client authentication method of clients of the provider.
     ///
     /// Although not recommended by the RFC, some providers require `client_id` and `client_secret`
-    /// as part of the request body.
+    /// as part of the request body, with `ClientAuth::ClientSecretPost`.
     ///
     /// See [RFC 6749, section 2.3.1](http://tools.ietf.org/html/rfc6749#section-2.3.1).
-    fn credentials_in_body(&self) -> bool { false }
+    fn client_auth(&self) -> ClientAuth { ClientAuth::ClientSecretBasic }
 }
 
 /// Google OAuth 2.0 providers.
@@ -42,6 +96,9 @@ pub mod google {
     /// Signals the server to return the authorization code by prompting the user to copy and
     /// paste.
     ///
+    /// Google no longer supports this for new clients; use a loopback redirect instead, such as
+    /// `Client::authorize_loopback` with the `loopback` feature.
+    ///
     /// See [Choosing a redirect URI][uri].
     ///
     /// [uri]: https://developers.google.com/identity/protocols/OAuth2InstalledApp#choosingredirecturi
@@ -54,9 +111,16 @@ pub mod google {
     /// [uri]: https://developers.google.com/identity/protocols/OAuth2InstalledApp#choosingredirecturi
     pub const REDIRECT_URI_OOB_AUTO: &str = "urn:ietf:wg:oauth:2.0:oob:auto";
 
+    /// Issuer identifier of Google's authorization server.
+    pub const ISSUER: &str = "https://accounts.google.com";
+
     lazy_static! {
         static ref AUTH_URI: Url = Url::parse("https://accounts.google.com/o/oauth2/v2/auth").unwrap();
         static ref TOKEN_URI: Url = Url::parse("https://www.googleapis.com/oauth2/v4/token").unwrap();
+        static ref DEVICE_AUTHORIZATION_URI: Url = Url::parse("https://oauth2.googleapis.com/device/code").unwrap();
+        static ref REVOCATION_URI: Url = Url::parse("https://oauth2.googleapis.com/revoke").unwrap();
+        static ref JWKS_URI: Url = Url::parse("https://www.googleapis.com/oauth2/v3/certs").unwrap();
+        static ref USERINFO_URI: Url = Url::parse("https://openidconnect.googleapis.com/v1/userinfo").unwrap();
     }
 
     /// Google OAuth 2.0 provider for web applications.
@@ -68,27 +132,41 @@ pub mod google {
     impl Provider for Web {
         type Lifetime = Expiring;
         type Token = Bearer<Expiring>;
+        fn issuer(&self) -> Option<&str> { Some(ISSUER) }
         fn auth_uri(&self) -> &Url { &AUTH_URI }
         fn token_uri(&self) -> &Url { &TOKEN_URI }
+        fn revocation_uri(&self) -> Option<&Url> { Some(&REVOCATION_URI) }
+        fn jwks_uri(&self) -> Option<&Url> { Some(&JWKS_URI) }
+        fn userinfo_uri(&self) -> Option<&Url> { Some(&USERINFO_URI) }
     }
 
     /// Google OAuth 2.0 provider for installed applications.
     ///
     /// See [Using OAuth 2.0 for Installed
     /// Applications](https://developers.google.com/identity/protocols/OAuth2InstalledApp).
+    ///
+    /// The device authorization grant requires a client of the "TVs and Limited Input devices"
+    /// type. See [OAuth 2.0 for TV and Limited-Input Device
+    /// Applications](https://developers.google.com/identity/protocols/oauth2/limited-input-device).
     #[derive(Debug, Clone, Copy, PartialEq, Eq)]
     pub struct Installed;
     impl Provider for Installed {
         type Lifetime = Refresh;
         type Token = Bearer<Refresh>;
+        fn issuer(&self) -> Option<&str> { Some(ISSUER) }
         fn auth_uri(&self) -> &Url { &AUTH_URI }
         fn token_uri(&self) -> &Url { &TOKEN_URI }
+        fn device_authorization_uri(&self) -> Option<&Url> { Some(&DEVICE_AUTHORIZATION_URI) }
+        fn revocation_uri(&self) -> Option<&Url> { Some(&REVOCATION_URI) }
+        fn jwks_uri(&self) -> Option<&Url> { Some(&JWKS_URI) }
+        fn userinfo_uri(&self) -> Option<&Url> { Some(&USERINFO_URI) }
     }
 }
 
 lazy_static! {
     static ref GITHUB_AUTH_URI: Url = Url::parse("https://github.com/login/oauth/authorize").unwrap();
     static ref GITHUB_TOKEN_URI: Url = Url::parse("https://github.com/login/oauth/access_token").unwrap();
+    static ref GITHUB_DEVICE_AUTHORIZATION_URI: Url = Url::parse("https://github.com/login/device/code").unwrap();
     static ref IMGUR_AUTH_URI: Url = Url::parse("https://api.imgur.com/oauth2/authorize").unwrap();
     static ref IMGUR_TOKEN_URI: Url = Url::parse("https://api.imgur.com/oauth2/token").unwrap();
 }
@@ -96,6 +174,8 @@ lazy_static! {
 /// GitHub OAuth 2.0 provider.
 ///
 /// See [OAuth, GitHub Developer Guide](https://developer.github.com/v3/oauth/).
+///
+/// GitHub has no RFC 7009 revocation endpoint; revoke tokens with `Client::revoke_github_token`.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct GitHub;
 impl Provider for GitHub {
@@ -103,6 +183,26 @@ impl Provider for GitHub {
     type Token = Bearer<Static>;
     fn auth_uri(&self) -> &Url { &GITHUB_AUTH_URI }
     fn token_uri(&self) -> &Url { &GITHUB_TOKEN_URI }
+    fn device_authorization_uri(&self) -> Option<&Url> { Some(&GITHUB_DEVICE_AUTHORIZATION_URI) }
+}
+impl GitHub {
+    /// Builds a request to revoke `token` with the [Delete an app
+    /// token](https://docs.github.com/en/rest/apps/oauth-applications#delete-an-app-token) API.
+    ///
+    /// The API always authenticates the application with HTTP Basic authentication.
+    pub fn revocation_request(
+        &self,
+        client_id: &str,
+        client_secret: &str,
+        token: &str,
+    ) -> HttpRequest {
+        let url = format!("https://api.github.com/applications/{}/token", client_id);
+        HttpRequest::new(Method::Delete, url)
+            .basic_auth(client_id, client_secret)
+            .header(ACCEPT, "application/vnd.github+json")
+            .header(USER_AGENT, "inth-oauth2-async")
+            .json(&json!({ "access_token": token }))
+    }
 }
 
 /// Imgur OAuth 2.0 provider.
@@ -120,11 +220,19 @@ impl Provider for Imgur {
 #[test]
 fn google_urls() {
     let prov = google::Web;
+    prov.issuer();
     prov.auth_uri();
     prov.token_uri();
+    prov.revocation_uri();
+    prov.jwks_uri();
+    prov.userinfo_uri();
     let prov = google::Installed;
     prov.auth_uri();
     prov.token_uri();
+    prov.device_authorization_uri();
+    prov.revocation_uri();
+    prov.jwks_uri();
+    prov.userinfo_uri();
 }
 
 #[test]
@@ -132,6 +240,7 @@ fn github_urls() {
     let prov = GitHub;
     prov.auth_uri();
     prov.token_uri();
+    prov.device_authorization_uri();
 }
 
 #[test]
@@ -0,0 +1,360 @@
+//! Authorization server metadata discovery.
+//!
+//! See [RFC 8414](https://tools.ietf.org/html/rfc8414) and [OpenID Connect Discovery
+//! 1.0](https://openid.net/specs/openid-connect-discovery-1_0.html).
+
+use std::marker::PhantomData;
+
+use serde_json::{Map, Value};
+use url::Url;
+
+use crate::client::response::{FromResponse, ParseError};
+use crate::client::{ClientAuth, ClientError, HttpClient};
+use crate::provider::Provider;
+use crate::token::{Bearer, Lifetime};
+
+/// Authorization server metadata.
+///
+/// Lists which are absent from the metadata are empty.
+///
+/// See [RFC 8414, section 2](https://tools.ietf.org/html/rfc8414#section-2).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Metadata {
+    /// The authorization server's issuer identifier.
+    pub issuer: String,
+
+    /// The authorization endpoint URI.
+    pub authorization_endpoint: Url,
+
+    /// The token endpoint URI.
+    pub token_endpoint: Url,
+
+    /// The JSON Web Key Set document URI.
+    pub jwks_uri: Option<Url>,
+
+    /// The OpenID Connect UserInfo endpoint URI.
+    pub userinfo_endpoint: Option<Url>,
+
+    /// The dynamic client registration endpoint URI.
+    pub registration_endpoint: Option<Url>,
+
+    /// The token revocation endpoint URI.
+    pub revocation_endpoint: Option<Url>,
+
+    /// The token introspection endpoint URI.
+    pub introspection_endpoint: Option<Url>,
+
+    /// The device authorization endpoint URI.
+    pub device_authorization_endpoint: Option<Url>,
+
+    /// The pushed authorization request endpoint URI.
+    pub pushed_authorization_request_endpoint: Option<Url>,
+
+    /// Whether the server only accepts authorization requests pushed to
+    /// `pushed_authorization_request_endpoint`.
+    #[serde(default)]
+    pub require_pushed_authorization_requests: bool,
+
+    /// Supported scope values.
+    #[serde(default)]
+    pub scopes_supported: Vec<String>,
+
+    /// Supported `response_type` values.
+    #[serde(default)]
+    pub response_types_supported: Vec<String>,
+
+    /// Supported grant types. Absent means `authorization_code` and `implicit`.
+    #[serde(default)]
+    pub grant_types_supported: Vec<String>,
+
+    /// Supported token endpoint client authentication methods. Absent means
+    /// `client_secret_basic`.
+    #[serde(default)]
+    pub token_endpoint_auth_methods_supported: Vec<String>,
+
+    /// Supported PKCE code challenge methods. Absent means PKCE is not supported.
+    #[serde(default)]
+    pub code_challenge_methods_supported: Vec<String>,
+
+    /// Whether authorization responses include the `iss` parameter.
+    ///
+    /// See [RFC 9207, section 3](https://tools.ietf.org/html/rfc9207#section-3).
+    #[serde(default)]
+    pub authorization_response_iss_parameter_supported: bool,
+
+    /// Remaining metadata.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl Metadata {
+    /// Returns true if the server supports the grant type.
+    pub fn supports_grant_type(&self, grant_type: &str) -> bool {
+        if self.grant_types_supported.is_empty() {
+            return grant_type == "authorization_code" || grant_type == "implicit";
+        }
+        self.grant_types_supported.iter().any(|g| g == grant_type)
+    }
+
+    /// Returns true if the server supports the token endpoint client authentication method.
+    pub fn supports_token_endpoint_auth_method(&self, method: &str) -> bool {
+        if self.token_endpoint_auth_methods_supported.is_empty() {
+            return method == "client_secret_basic";
+        }
+        self.token_endpoint_auth_methods_supported.iter().any(|m| m == method)
+    }
+
+    /// Returns true if the server supports the PKCE code challenge method.
+    pub fn supports_code_challenge_method(&self, method: &str) -> bool {
+        self.code_challenge_methods_supported.iter().any(|m| m == method)
+    }
+}
+
+impl FromResponse for Metadata {
+    fn from_response(json: &Value) -> Result<Self, ParseError> {
+        if !json.is_object() {
+            return Err(ParseError::ExpectedType("object"));
+        }
+        serde_json::from_value(json.clone())
+            .map_err(|_| ParseError::ExpectedType("authorization server metadata"))
+    }
+}
+
+/// Returns the metadata URIs of an issuer, in the order they are tried.
+///
+/// See [RFC 8414, section 3](https://tools.ietf.org/html/rfc8414#section-3) and [OpenID Connect
+/// Discovery 1.0, section 4](https://openid.net/specs/openid-connect-discovery-1_0.html#ProviderConfig).
+fn metadata_uris(issuer: &Url) -> [Url; 2] {
+    let path = issuer.path().trim_end_matches('/');
+
+    let mut oauth = issuer.clone();
+    oauth.set_path(&format!("/.well-known/oauth-authorization-server{}", path));
+    oauth.set_query(None);
+
+    let mut openid = issuer.clone();
+    openid.set_path(&format!("{}/.well-known/openid-configuration", path));
+    openid.set_query(None);
+
+    [oauth, openid]
+}
+
+/// A provider configured from its authorization server metadata.
+///
+/// `L` is the lifetime of the provider's tokens, which the metadata does not describe.
+///
+/// # Examples
+///
+/// ```no_run
+/// # #[cfg(feature="reqwest-client")] {
+/// use inth_oauth2_async::Client;
+/// use inth_oauth2_async::provider::DiscoveredProvider;
+/// use inth_oauth2_async::token::Refresh;
+/// # #[tokio::main]
+/// # async fn main() {
+///
+/// let http = reqwest::Client::new();
+/// let provider = DiscoveredProvider::<Refresh>::discover(&http, "https://accounts.google.com")
+///     .await
+///     .unwrap();
+/// let client = Client::new(provider, String::new(), String::new(), None);
+/// # } }
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DiscoveredProvider<L> {
+    metadata: Metadata,
+    #[serde(skip)]
+    lifetime: PhantomData<L>,
+}
+
+impl<L: Lifetime> DiscoveredProvider<L> {
+    /// Fetches the metadata of the authorization server with the given issuer identifier.
+    ///
+    /// The RFC 8414 well-known URI is tried first, then the OpenID Connect one. The issuer in the
+    /// metadata must be identical to `issuer`; if neither location has such metadata, fails with
+    /// `ClientError::IssuerMismatch` if either had metadata for another issuer.
+    pub async fn discover(
+        http_client: &impl HttpClient,
+        issuer: &str,
+    ) -> Result<Self, ClientError> {
+        let issuer_url = Url::parse(issuer)?;
+
+        let mut mismatch = None;
+        let mut error = None;
+        for uri in &metadata_uris(&issuer_url) {
+            let metadata = match http_client.get(uri.as_str()).await {
+                Ok(json) => Metadata::from_response(&json).map_err(ClientError::from),
+                Err(err) => Err(err),
+            };
+            match metadata {
+                Ok(metadata) if metadata.issuer == issuer => {
+                    return Ok(DiscoveredProvider::from_metadata(metadata));
+                }
+                // Servers may publish different metadata at each location, so try the other.
+                Ok(metadata) => {
+                    mismatch = Some(ClientError::IssuerMismatch {
+                        expected: issuer.to_owned(),
+                        found: metadata.issuer,
+                    });
+                }
+                Err(err) => error = Some(err),
+            }
+        }
+        Err(mismatch.or(error).expect("no metadata URIs"))
+    }
+
+    /// Creates a provider from metadata obtained elsewhere.
+    pub fn from_metadata(metadata: Metadata) -> Self {
+        DiscoveredProvider { metadata, lifetime: PhantomData }
+    }
+
+    /// Returns the authorization server metadata.
+    pub fn metadata(&self) -> &Metadata { &self.metadata }
+}
+
+impl<L: Lifetime> Provider for DiscoveredProvider<L> {
+    type Lifetime = L;
+    type Token = Bearer<L>;
+    fn issuer(&self) -> Option<&str> { Some(&self.metadata.issuer) }
+    fn authorization_response_iss_parameter_supported(&self) -> bool {
+        self.metadata.authorization_response_iss_parameter_supported
+    }
+    fn auth_uri(&self) -> &Url { &self.metadata.authorization_endpoint }
+    fn token_uri(&self) -> &Url { &self.metadata.token_endpoint }
+    fn client_auth(&self) -> ClientAuth {
+        if !self.metadata.supports_token_endpoint_auth_method("client_secret_basic")
+            && self.metadata.supports_token_endpoint_auth_method("client_secret_post")
+        {
+            ClientAuth::ClientSecretPost
+        } else {
+            ClientAuth::ClientSecretBasic
+        }
+    }
+    fn registration_uri(&self) -> Option<&Url> {
+        self.metadata.registration_endpoint.as_ref()
+    }
+    fn pushed_authorization_request_uri(&self) -> Option<&Url> {
+        self.metadata.pushed_authorization_request_endpoint.as_ref()
+    }
+    fn device_authorization_uri(&self) -> Option<&Url> {
+        self.metadata.device_authorization_endpoint.as_ref()
+    }
+    fn revocation_uri(&self) -> Option<&Url> { self.metadata.revocation_endpoint.as_ref() }
+    fn introspection_uri(&self) -> Option<&Url> { self.metadata.introspection_endpoint.as_ref() }
+    fn jwks_uri(&self) -> Option<&Url> { self.metadata.jwks_uri.as_ref() }
+    fn userinfo_uri(&self) -> Option<&Url> { self.metadata.userinfo_endpoint.as_ref() }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::http_client::HttpResponse;
+    use crate::client::test_util::MockHttp;
+    use crate::token::Refresh;
+
+    const METADATA: &str = r#"
+        {
+            "issuer":"https://server.example.com",
+            "authorization_endpoint":"https://server.example.com/authorize",
+            "token_endpoint":"https://server.example.com/token",
+            "token_endpoint_auth_methods_supported":["client_secret_post","private_key_jwt"],
+            "jwks_uri":"https://server.example.com/jwks.json",
+            "revocation_endpoint":"https://server.example.com/revoke",
+            "scopes_supported":["openid","profile"],
+            "response_types_supported":["code"],
+            "code_challenge_methods_supported":["S256"],
+            "authorization_response_iss_parameter_supported":true,
+            "op_policy_uri":"https://server.example.com/policy"
+        }
+    "#;
+
+    fn not_found() -> HttpResponse {
+        HttpResponse { status: 404, headers: Vec::new(), body: Vec::new() }
+    }
+
+    fn ok(body: &str) -> HttpResponse {
+        HttpResponse { status: 200, headers: Vec::new(), body: body.as_bytes().to_vec() }
+    }
+
+    #[test]
+    fn metadata_uris_with_path() {
+        let [oauth, openid] = metadata_uris(&Url::parse("https://example.com/issuer1/").unwrap());
+        assert_eq!("https://example.com/.well-known/oauth-authorization-server/issuer1", oauth.as_str());
+        assert_eq!("https://example.com/issuer1/.well-known/openid-configuration", openid.as_str());
+    }
+
+    #[test]
+    fn from_response() {
+        let metadata = Metadata::from_response(&METADATA.parse().unwrap()).unwrap();
+        assert_eq!("https://server.example.com/token", metadata.token_endpoint.as_str());
+        assert!(metadata.supports_code_challenge_method("S256"));
+        assert!(!metadata.supports_code_challenge_method("plain"));
+        assert!(metadata.supports_grant_type("authorization_code"));
+        assert!(!metadata.supports_grant_type("client_credentials"));
+        assert_eq!("https://server.example.com/policy", metadata.extra["op_policy_uri"]);
+    }
+
+    #[tokio::test]
+    async fn discover() {
+        let http = MockHttp::with_responses(vec![ok(METADATA)]);
+        let provider = DiscoveredProvider::<Refresh>::discover(&http, "https://server.example.com")
+            .await
+            .unwrap();
+        assert_eq!(
+            vec!["https://server.example.com/.well-known/oauth-authorization-server"],
+            http.urls()
+        );
+        assert_eq!("https://server.example.com/authorize", provider.auth_uri().as_str());
+        assert_eq!(
+            Some("https://server.example.com/revoke"),
+            provider.revocation_uri().map(Url::as_str)
+        );
+        assert!(matches!(provider.client_auth(), ClientAuth::ClientSecretPost));
+        assert_eq!(Some("https://server.example.com"), provider.issuer());
+        assert!(provider.authorization_response_iss_parameter_supported());
+    }
+
+    #[tokio::test]
+    async fn discover_openid_configuration() {
+        let http = MockHttp::with_responses(vec![not_found(), ok(METADATA)]);
+        DiscoveredProvider::<Refresh>::discover(&http, "https://server.example.com")
+            .await
+            .unwrap();
+        assert_eq!(
+            "https://server.example.com/.well-known/openid-configuration",
+            http.urls()[1]
+        );
+    }
+
+    #[tokio::test]
+    async fn discover_issuer_mismatch() {
+        let http = MockHttp::with_responses(vec![ok(METADATA)]);
+        let result = DiscoveredProvider::<Refresh>::discover(&http, "https://attacker.example.com")
+            .await;
+        assert!(matches!(result, Err(ClientError::IssuerMismatch { .. })));
+        assert_eq!(2, http.urls().len());
+
+        let http = MockHttp::with_responses(vec![ok(METADATA), not_found()]);
+        let result = DiscoveredProvider::<Refresh>::discover(&http, "https://attacker.example.com")
+            .await;
+        assert!(matches!(result, Err(ClientError::IssuerMismatch { .. })));
+    }
+
+    #[tokio::test]
+    async fn discover_issuer_mismatch_then_openid_configuration() {
+        let other = METADATA.replace("https://server.example.com\"", "https://other.example.com\"");
+        let http = MockHttp::with_responses(vec![ok(&other), ok(METADATA)]);
+        let provider = DiscoveredProvider::<Refresh>::discover(&http, "https://server.example.com")
+            .await
+            .unwrap();
+        assert_eq!(2, http.urls().len());
+        assert_eq!(Some("https://server.example.com"), provider.issuer());
+    }
+
+    #[tokio::test]
+    async fn discover_not_found() {
+        let http = MockHttp::with_responses(vec![not_found()]);
+        let result = DiscoveredProvider::<Refresh>::discover(&http, "https://server.example.com")
+            .await;
+        assert!(matches!(result, Err(ClientError::HttpStatus(404))));
+    }
+}
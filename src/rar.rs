@@ -0,0 +1,174 @@
+//! Rich authorization requests.
+//!
+//! Fine-grained permissions are requested with a JSON array of authorization details, in
+//! authorization and token requests, instead of or as well as a scope. The details granted are
+//! returned by [`Token::authorization_details`](crate::token::Token::authorization_details).
+//!
+//! ```
+//! use inth_oauth2_async::Client;
+//! use inth_oauth2_async::client::AuthParams;
+//! use inth_oauth2_async::provider::google::Web;
+//! use inth_oauth2_async::rar::AuthorizationDetail;
+//! # let client = Client::new(Web, String::new(), String::new(), None);
+//!
+//! let details = [
+//!     AuthorizationDetail::new("payment_initiation")
+//!         .with_actions(&["initiate", "status"])
+//!         .with_locations(&["https://example.com/payments"])
+//!         .with_field(
+//!             "instructedAmount",
+//!             serde_json::json!({"currency": "EUR", "amount": "123.50"}),
+//!         ),
+//! ];
+//! let auth_uri = client.auth_uri_with(&AuthParams {
+//!     authorization_details: Some(&details),
+//!     ..AuthParams::default()
+//! });
+//! ```
+//!
+//! See [RFC 9396](https://tools.ietf.org/html/rfc9396).
+
+use serde_json::{Map, Value};
+
+/// An authorization details object.
+///
+/// Fields other than the common data fields are specific to the `type` and kept in `extra`.
+///
+/// See [RFC 9396, section 2](https://tools.ietf.org/html/rfc9396#section-2).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuthorizationDetail {
+    /// The type of authorization data, which determines the other fields allowed.
+    #[serde(rename = "type")]
+    pub type_: String,
+
+    /// Locations of the resource or resource server, typically URIs.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub locations: Vec<String>,
+
+    /// Kinds of actions to be taken at the resource.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub actions: Vec<String>,
+
+    /// Kinds of data being requested from the resource.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub datatypes: Vec<String>,
+
+    /// Identifier of a specific resource available at the API.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub identifier: Option<String>,
+
+    /// Types or levels of privilege being requested at the resource.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub privileges: Vec<String>,
+
+    /// Type-specific fields.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl AuthorizationDetail {
+    /// Creates an authorization details object of the given type, with no other fields.
+    pub fn new(type_: impl Into<String>) -> Self {
+        AuthorizationDetail {
+            type_: type_.into(),
+            locations: Vec::new(),
+            actions: Vec::new(),
+            datatypes: Vec::new(),
+            identifier: None,
+            privileges: Vec::new(),
+            extra: Map::new(),
+        }
+    }
+
+    /// Sets the locations.
+    pub fn with_locations<S: AsRef<str>>(mut self, locations: &[S]) -> Self {
+        self.locations = locations.iter().map(|s| s.as_ref().to_owned()).collect();
+        self
+    }
+
+    /// Sets the actions.
+    pub fn with_actions<S: AsRef<str>>(mut self, actions: &[S]) -> Self {
+        self.actions = actions.iter().map(|s| s.as_ref().to_owned()).collect();
+        self
+    }
+
+    /// Sets the datatypes.
+    pub fn with_datatypes<S: AsRef<str>>(mut self, datatypes: &[S]) -> Self {
+        self.datatypes = datatypes.iter().map(|s| s.as_ref().to_owned()).collect();
+        self
+    }
+
+    /// Sets the identifier.
+    pub fn with_identifier(mut self, identifier: impl Into<String>) -> Self {
+        self.identifier = Some(identifier.into());
+        self
+    }
+
+    /// Sets the privileges.
+    pub fn with_privileges<S: AsRef<str>>(mut self, privileges: &[S]) -> Self {
+        self.privileges = privileges.iter().map(|s| s.as_ref().to_owned()).collect();
+        self
+    }
+
+    /// Sets a type-specific field.
+    pub fn with_field(mut self, name: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.extra.insert(name.into(), value.into());
+        self
+    }
+
+    /// Returns a type-specific field.
+    pub fn field(&self, name: &str) -> Option<&Value> {
+        self.extra.get(name)
+    }
+}
+
+/// Serializes authorization details as the `authorization_details` parameter value.
+pub(crate) fn to_param(details: &[AuthorizationDetail]) -> String {
+    serde_json::to_string(details).expect("authorization details serialize to JSON")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn serialize() {
+        let details = [
+            AuthorizationDetail::new("account_information")
+                .with_actions(&["list_accounts", "read_balances"])
+                .with_locations(&["https://example.com/accounts"]),
+            AuthorizationDetail::new("payment_initiation")
+                .with_identifier("XYZ")
+                .with_field("creditorName", "Merchant A"),
+        ];
+        assert_eq!(
+            json!([
+                {
+                    "type": "account_information",
+                    "locations": ["https://example.com/accounts"],
+                    "actions": ["list_accounts", "read_balances"],
+                },
+                {
+                    "type": "payment_initiation",
+                    "identifier": "XYZ",
+                    "creditorName": "Merchant A",
+                },
+            ]),
+            serde_json::from_str::<Value>(&to_param(&details)).unwrap()
+        );
+    }
+
+    #[test]
+    fn deserialize() {
+        let detail: AuthorizationDetail = serde_json::from_value(json!({
+            "type": "payment_initiation",
+            "actions": ["initiate"],
+            "instructedAmount": {"currency": "EUR", "amount": "123.50"},
+        })).unwrap();
+        assert_eq!("payment_initiation", detail.type_);
+        assert_eq!(vec!["initiate"], detail.actions);
+        assert!(detail.locations.is_empty());
+        assert_eq!(Some(&json!("EUR")), detail.field("instructedAmount").map(|v| &v["currency"]));
+    }
+}
@@ -0,0 +1,332 @@
+//! Stateless `state` parameters.
+//!
+//! Instead of storing the state, PKCE verifier and nonce of each authorization request in a
+//! server-side session, [`StateCodec`] packs them into the `state` parameter itself, signed with
+//! HMAC-SHA256 and optionally encrypted with ChaCha20-Poly1305. Any instance holding the same keys
+//! can verify the callback.
+//!
+//! States carrying a PKCE verifier must be encrypted: the `state` parameter travels through the
+//! user agent alongside the code challenge, and a readable verifier would defeat PKCE.
+//!
+//! The state's nonce must also be kept in the user agent's session, such as in a cookie, and
+//! passed to [`StateCodec::verify`]. This binds the state to the user agent, so an attacker
+//! cannot complete the flow with a state issued to their own session.
+//!
+//! ```
+//! use std::time::Duration;
+//! use inth_oauth2_async::Client;
+//! use inth_oauth2_async::client::{AuthParams, AuthorizationResponse};
+//! use inth_oauth2_async::pkce::Pkce;
+//! use inth_oauth2_async::provider::google::Web;
+//! use inth_oauth2_async::state::{AuthState, StateCodec};
+//! # let client = Client::new(Web, String::new(), String::new(), None);
+//!
+//! let codec = StateCodec::new(b"an HMAC key of at least 32 bytes")
+//!     .with_encryption_key(b"a ChaCha20-Poly1305 key 32 bytes")
+//!     .with_max_age(Duration::from_secs(300));
+//!
+//! let auth_state = AuthState::new(Some(Pkce::s256()), "/return/to".into());
+//! let state = codec.encode(&auth_state).unwrap();
+//! // Set in a cookie, e.g. `Set-Cookie: __Host-oauth-nonce=...; Secure; HttpOnly; SameSite=Lax`.
+//! let session_nonce = auth_state.nonce.as_str().to_owned();
+//! let auth_uri = client.auth_uri_with(&AuthParams {
+//!     state: Some(&state),
+//!     pkce: auth_state.pkce.as_ref(),
+//!     ..AuthParams::default()
+//! });
+//!
+//! // In the callback, on any instance:
+//! # let callback = url::Url::parse(&format!("https://example.com/cb?code=c&state={}", state)).unwrap();
+//! let response = AuthorizationResponse::from_url(&callback).unwrap();
+//! client.verify_issuer(&response).unwrap();
+//! let auth_state = codec.verify(&response, &session_nonce).unwrap();
+//! assert_eq!("/return/to", auth_state.payload);
+//! ```
+
+use std::error::Error;
+use std::fmt;
+use std::time::Duration;
+
+use ring::aead::{self, Aad, LessSafeKey, UnboundKey, CHACHA20_POLY1305, NONCE_LEN};
+use ring::hmac;
+use serde_json::Value;
+
+use crate::client::{AuthorizationResponse, ClientError};
+use crate::jwt;
+use crate::oidc::Nonce;
+use crate::pkce::Pkce;
+use crate::util::{base64url, constant_time_eq, random_bytes};
+
+/// Default maximum age of a state value.
+const DEFAULT_MAX_AGE: Duration = Duration::from_secs(10 * 60);
+
+/// Allowed clock skew between instances issuing and verifying state values.
+const LEEWAY: u64 = 60;
+
+/// The contents of a stateless `state` parameter.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuthState {
+    /// Random value making each state unique, which can also serve as the OpenID Connect nonce.
+    pub nonce: Nonce,
+
+    /// Issue time, in seconds since the Unix epoch.
+    pub issued_at: u64,
+
+    /// PKCE verifier of the authorization request.
+    pub pkce: Option<Pkce>,
+
+    /// Application data, such as the URL to return to after login.
+    pub payload: Value,
+}
+
+impl AuthState {
+    /// Creates a state issued now, with a random nonce.
+    pub fn new(pkce: Option<Pkce>, payload: Value) -> Self {
+        AuthState {
+            nonce: Nonce::new(),
+            issued_at: jwt::now(),
+            pkce,
+            payload,
+        }
+    }
+}
+
+/// State encoding errors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StateError {
+    /// The state has a PKCE verifier and the codec has no encryption key.
+    UnencryptedVerifier,
+
+    /// Encryption failed.
+    Encryption,
+}
+
+impl fmt::Display for StateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match *self {
+            StateError::UnencryptedVerifier => {
+                write!(f, "States with a PKCE verifier must be encrypted")
+            }
+            StateError::Encryption => write!(f, "Encryption failed"),
+        }
+    }
+}
+
+impl Error for StateError {
+    fn description(&self) -> &str { "state error" }
+}
+
+/// Signs and verifies stateless `state` parameters.
+///
+/// The encoded value is `BASE64URL(body) "." BASE64URL(HMAC-SHA256(body))`, where the body is the
+/// JSON-serialized [`AuthState`], or its encryption if an encryption key is set.
+#[derive(Debug)]
+pub struct StateCodec {
+    signing_key: hmac::Key,
+    encryption_key: Option<LessSafeKey>,
+    max_age: Duration,
+}
+
+impl StateCodec {
+    /// Creates a codec signing with `key`, which should be at least 32 random bytes.
+    pub fn new(key: &[u8]) -> Self {
+        StateCodec {
+            signing_key: hmac::Key::new(hmac::HMAC_SHA256, key),
+            encryption_key: None,
+            max_age: DEFAULT_MAX_AGE,
+        }
+    }
+
+    /// Also encrypts states with a 32-byte ChaCha20-Poly1305 key, hiding the PKCE verifier and
+    /// payload from the user agent. Required to encode states with a PKCE verifier.
+    pub fn with_encryption_key(mut self, key: &[u8; 32]) -> Self {
+        let key = UnboundKey::new(&CHACHA20_POLY1305, key).expect("key length is correct");
+        self.encryption_key = Some(LessSafeKey::new(key));
+        self
+    }
+
+    /// Sets the maximum age of accepted states. Defaults to ten minutes.
+    pub fn with_max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = max_age;
+        self
+    }
+
+    /// Encodes a state as a `state` parameter value.
+    ///
+    /// Fails with `StateError::UnencryptedVerifier` if the state has a PKCE verifier and no
+    /// encryption key is set, since the verifier would be readable by anyone seeing the
+    /// authorization request.
+    pub fn encode(&self, state: &AuthState) -> Result<String, StateError> {
+        if state.pkce.is_some() && self.encryption_key.is_none() {
+            return Err(StateError::UnencryptedVerifier);
+        }
+        // Serializing to JSON only fails for maps with non-string keys, which `AuthState` and
+        // `Value` cannot have.
+        let mut body = serde_json::to_vec(state).expect("state serializes to JSON");
+        if let Some(ref key) = self.encryption_key {
+            let nonce = random_bytes(NONCE_LEN);
+            let aead_nonce = aead::Nonce::try_assume_unique_for_key(&nonce)
+                .map_err(|_| StateError::Encryption)?;
+            key.seal_in_place_append_tag(aead_nonce, Aad::empty(), &mut body)
+                .map_err(|_| StateError::Encryption)?;
+            body.splice(0..0, nonce);
+        }
+        let tag = hmac::sign(&self.signing_key, &body);
+        Ok(format!("{}.{}", base64url(&body), base64url(tag)))
+    }
+
+    /// Decodes and verifies a `state` parameter value.
+    ///
+    /// Fails with `ClientError::StateMismatch` if the value was not produced by this codec's keys,
+    /// or `ClientError::StateExpired` if it is older than the maximum age. This does not check that
+    /// the state was issued to the user agent presenting it; use [`StateCodec::verify`] for that.
+    pub fn decode(&self, value: &str) -> Result<AuthState, ClientError> {
+        let decode = |part| {
+            base64::decode_config(part, base64::URL_SAFE_NO_PAD)
+                .map_err(|_| ClientError::StateMismatch)
+        };
+        let (body, tag) = value.split_once('.').ok_or(ClientError::StateMismatch)?;
+        let mut body = decode(body)?;
+        hmac::verify(&self.signing_key, &body, &decode(tag)?)
+            .map_err(|_| ClientError::StateMismatch)?;
+
+        if let Some(ref key) = self.encryption_key {
+            if body.len() < NONCE_LEN {
+                return Err(ClientError::StateMismatch);
+            }
+            let mut ciphertext = body.split_off(NONCE_LEN);
+            let nonce = aead::Nonce::try_assume_unique_for_key(&body).unwrap();
+            let len = key.open_in_place(nonce, Aad::empty(), &mut ciphertext)
+                .map_err(|_| ClientError::StateMismatch)?
+                .len();
+            ciphertext.truncate(len);
+            body = ciphertext;
+        }
+
+        let state: AuthState =
+            serde_json::from_slice(&body).map_err(|_| ClientError::StateMismatch)?;
+        let now = jwt::now();
+        if state.issued_at > now + LEEWAY {
+            return Err(ClientError::StateMismatch);
+        }
+        if state.issued_at + self.max_age.as_secs() < now {
+            return Err(ClientError::StateExpired);
+        }
+        Ok(state)
+    }
+
+    /// Verifies the `state` parameter of an authorization response, and that it was issued to the
+    /// user agent's session.
+    ///
+    /// `session_nonce` is the [`AuthState::nonce`] kept in the user agent's session, typically a
+    /// cookie, when the authorization request was made. It is required: a validly signed state
+    /// alone may have been issued to an attacker's session, and accepting it would log the user in
+    /// to the attacker's account.
+    ///
+    /// See [RFC 6749, section 10.12](http://tools.ietf.org/html/rfc6749#section-10.12).
+    pub fn verify(
+        &self,
+        response: &AuthorizationResponse,
+        session_nonce: &str,
+    ) -> Result<AuthState, ClientError> {
+        let state = response.state.as_ref().ok_or(ClientError::StateMismatch)?;
+        let state = self.decode(state)?;
+        if !constant_time_eq(state.nonce.as_str().as_bytes(), session_nonce.as_bytes()) {
+            return Err(ClientError::StateMismatch);
+        }
+        Ok(state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const KEY: &[u8] = b"0123456789abcdef0123456789abcdef";
+
+    fn state() -> AuthState {
+        AuthState::new(Some(Pkce::s256()), json!({"return_to": "/home"}))
+    }
+
+    fn codec() -> StateCodec {
+        StateCodec::new(KEY).with_encryption_key(&[7; 32])
+    }
+
+    #[test]
+    fn round_trip() {
+        let codec = StateCodec::new(KEY);
+        let state = AuthState::new(None, json!({"return_to": "/home"}));
+        let value = codec.encode(&state).unwrap();
+        assert_eq!(state, codec.decode(&value).unwrap());
+        assert_ne!(value, codec.encode(&AuthState::new(None, Value::Null)).unwrap());
+    }
+
+    #[test]
+    fn unencrypted_pkce() {
+        assert_eq!(Err(StateError::UnencryptedVerifier), StateCodec::new(KEY).encode(&state()));
+    }
+
+    #[test]
+    fn round_trip_encrypted() {
+        let codec = codec();
+        let state = state();
+        let value = codec.encode(&state).unwrap();
+        assert_eq!(state, codec.decode(&value).unwrap());
+
+        let (body, _) = value.split_once('.').unwrap();
+        let body = base64::decode_config(body, base64::URL_SAFE_NO_PAD).unwrap();
+        assert!(!String::from_utf8_lossy(&body).contains("return_to"));
+    }
+
+    #[test]
+    fn tampered() {
+        let codec = codec();
+        let value = codec.encode(&state()).unwrap();
+        let (body, tag) = value.split_once('.').unwrap();
+        let forged = format!("{}.{}", base64url(r#"{"nonce":"x"}"#), tag);
+
+        assert!(matches!(codec.decode(&forged), Err(ClientError::StateMismatch)));
+        assert!(matches!(codec.decode(body), Err(ClientError::StateMismatch)));
+        assert!(matches!(
+            StateCodec::new(b"another key").with_encryption_key(&[7; 32]).decode(&value),
+            Err(ClientError::StateMismatch)
+        ));
+        assert!(matches!(
+            StateCodec::new(KEY).with_encryption_key(&[8; 32]).decode(&value),
+            Err(ClientError::StateMismatch)
+        ));
+        assert!(matches!(StateCodec::new(KEY).decode(&value), Err(ClientError::StateMismatch)));
+    }
+
+    #[test]
+    fn expired() {
+        let codec = codec().with_max_age(Duration::from_secs(60));
+        let state = AuthState { issued_at: jwt::now() - 120, ..state() };
+        let value = codec.encode(&state).unwrap();
+        assert!(matches!(codec.decode(&value), Err(ClientError::StateExpired)));
+    }
+
+    #[test]
+    fn verify_response() {
+        let codec = codec();
+        let state = state();
+        let response = AuthorizationResponse {
+            code: String::from("abc"),
+            state: Some(codec.encode(&state).unwrap()),
+            extra: Vec::new(),
+        };
+        let nonce = state.nonce.as_str();
+        assert_eq!(state, codec.verify(&response, nonce).unwrap());
+
+        let other_session = AuthState::new(None, Value::Null);
+        assert!(matches!(
+            codec.verify(&response, other_session.nonce.as_str()),
+            Err(ClientError::StateMismatch)
+        ));
+        assert!(matches!(codec.verify(&response, ""), Err(ClientError::StateMismatch)));
+
+        let response = AuthorizationResponse { state: None, ..response };
+        assert!(matches!(codec.verify(&response, nonce), Err(ClientError::StateMismatch)));
+    }
+}
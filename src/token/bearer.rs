@@ -1,6 +1,7 @@
 use serde_json::Value;
 
 use crate::client::response::{FromResponse, ParseError};
+use crate::rar::AuthorizationDetail;
 use crate::token::{Token, Lifetime};
 
 /// The bearer token type.
@@ -11,6 +12,7 @@ pub struct Bearer<L: Lifetime> {
     access_token: String,
     scope: Option<String>,
     id_token: Option<String>,
+    authorization_details: Option<Vec<AuthorizationDetail>>,
     lifetime: L,
 }
 
@@ -27,6 +29,9 @@ impl<L: Lifetime> Token<L> for Bearer<L> {
     fn lifetime(&self) -> &L {
         &self.lifetime
     }
+    fn authorization_details(&self) -> Option<&[AuthorizationDetail]> {
+        self.authorization_details.as_ref().map(|d| &d[..])
+    }
 }
 
 impl<L: Lifetime> Bearer<L> {
@@ -45,11 +50,19 @@ impl<L: Lifetime> Bearer<L> {
             .ok_or(ParseError::ExpectedFieldType("access_token", "string"))?;
         let scope = obj.get("scope").and_then(Value::as_str);
         let id_token = obj.get("id_token").and_then(Value::as_str);
+        let authorization_details = match obj.get("authorization_details") {
+            Some(details) => Some(
+                serde_json::from_value(details.clone())
+                    .map_err(|_| ParseError::ExpectedFieldType("authorization_details", "array"))?,
+            ),
+            None => None,
+        };
 
         Ok(Bearer {
             access_token: access_token.into(),
             scope: scope.map(Into::into),
             id_token: id_token.map(Into::into),
+            authorization_details,
             lifetime,
         })
     }
@@ -90,6 +103,7 @@ mod tests {
                 access_token: String::from("aaaaaaaa"),
                 scope: None,
                 id_token: None,
+                authorization_details: None,
                 lifetime: Static,
             },
             Bearer::<Static>::from_response(&json).unwrap()
@@ -104,6 +118,7 @@ mod tests {
                 access_token: String::from("aaaaaaaa"),
                 scope: None,
                 id_token: None,
+                authorization_details: None,
                 lifetime: Static,
             },
             Bearer::<Static>::from_response(&json).unwrap()
@@ -120,12 +135,39 @@ mod tests {
                 access_token: String::from("aaaaaaaa"),
                 scope: Some(String::from("foo")),
                 id_token: None,
+                authorization_details: None,
                 lifetime: Static,
             },
             Bearer::<Static>::from_response(&json).unwrap()
         );
     }
 
+    #[test]
+    fn from_response_with_authorization_details() {
+        let json = r#"
+            {
+                "token_type":"Bearer",
+                "access_token":"aaaaaaaa",
+                "authorization_details":[
+                    {"type":"payment_initiation","actions":["initiate"],"creditorName":"A"}
+                ]
+            }
+        "#.parse().unwrap();
+        let bearer = Bearer::<Static>::from_response(&json).unwrap();
+        let details = bearer.authorization_details().unwrap();
+        assert_eq!(1, details.len());
+        assert_eq!("payment_initiation", details[0].type_);
+        assert_eq!(Some(&Value::from("A")), details[0].field("creditorName"));
+
+        let json = r#"{"token_type":"Bearer","access_token":"a","authorization_details":"x"}"#
+            .parse()
+            .unwrap();
+        assert_eq!(
+            ParseError::ExpectedFieldType("authorization_details", "array"),
+            Bearer::<Static>::from_response(&json).unwrap_err()
+        );
+    }
+
     #[test]
     fn from_response_refresh() {
         let json = r#"
@@ -16,6 +16,7 @@ pub use self::refresh::Refresh;
 pub use self::statik::Static;
 
 use crate::client::response::FromResponse;
+use crate::rar::AuthorizationDetail;
 
 /// OAuth 2.0 tokens.
 ///
@@ -34,10 +35,18 @@ pub trait Token<L: Lifetime>: FromResponse {
 
     /// Returns the token lifetime.
     fn lifetime(&self) -> &L;
+
+    /// Returns the authorization details granted, if available.
+    ///
+    /// See [RFC 9396, section 7](https://tools.ietf.org/html/rfc9396#section-7).
+    fn authorization_details(&self) -> Option<&[AuthorizationDetail]> { None }
 }
 
 /// OAuth 2.0 token lifetimes.
 pub trait Lifetime: FromResponse {
     /// Returns true if the access token is no longer valid.
     fn expired(&self) -> bool;
+
+    /// Returns the refresh token, if this lifetime carries one.
+    fn refresh_token(&self) -> Option<&str> { None }
 }
@@ -23,6 +23,7 @@ impl Refresh {
 
 impl Lifetime for Refresh {
     fn expired(&self) -> bool { self.expires < SystemTime::now() }
+    fn refresh_token(&self) -> Option<&str> { Some(&self.refresh_token) }
 }
 
 impl FromResponse for Refresh {
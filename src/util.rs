@@ -0,0 +1,27 @@
+//! Internal helpers.
+
+use ring::rand::{SecureRandom, SystemRandom};
+
+/// Encodes bytes as unpadded base64url.
+pub(crate) fn base64url(bytes: impl AsRef<[u8]>) -> String {
+    base64::encode_config(bytes, base64::URL_SAFE_NO_PAD)
+}
+
+/// Returns `len` bytes from the system CSPRNG.
+pub(crate) fn random_bytes(len: usize) -> Vec<u8> {
+    let mut bytes = vec![0; len];
+    SystemRandom::new()
+        .fill(&mut bytes)
+        .expect("system random number generator failed");
+    bytes
+}
+
+/// Returns `len` bytes from the system CSPRNG, encoded as unpadded base64url.
+pub(crate) fn random_token(len: usize) -> String {
+    base64url(random_bytes(len))
+}
+
+/// Compares two byte strings in time independent of their contents.
+pub(crate) fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}